@@ -0,0 +1,19 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+pub use contract::events::ContractEvent;
+
+const DATA_PREFIX: &str = "Program data: ";
+
+/// Decodes a single transaction log line emitted by `sol_log_data`.
+/// Lines that are not program data, or not a `ContractEvent`, are skipped.
+pub fn decode_log_line(line: &str) -> Option<ContractEvent> {
+    let encoded = line.strip_prefix(DATA_PREFIX)?;
+    // Each logged slice is base64-encoded and separated by a space; the
+    // program logs every event as a single slice.
+    let bytes = STANDARD.decode(encoded.split(' ').next()?).ok()?;
+    ContractEvent::decode(&bytes)
+}
+
+/// Decodes every contract event in a transaction's log messages, in order.
+pub fn decode_logs<S: AsRef<str>>(logs: &[S]) -> Vec<ContractEvent> {
+    logs.iter().filter_map(|line| decode_log_line(line.as_ref())).collect()
+}
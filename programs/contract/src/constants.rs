@@ -2,5 +2,87 @@ pub const GLOBAL_SEED: &[u8] = b"global";
 pub const OBJECT_SEED: &[u8] = b"object";
 pub const EPOCH_SEED: &[u8] = b"epoch";
 pub const MINER_SEED: &[u8] = b"miner";
+pub const CHALLENGE_SEED: &[u8] = b"challenge";
+pub const TREASURY_SEED: &[u8] = b"treasury";
+pub const ESCROW_SEED: &[u8] = b"escrow";
+pub const STAKE_VAULT_SEED: &[u8] = b"stake_vault";
+pub const BOND_VAULT_SEED: &[u8] = b"bond_vault";
 pub const TAPE_MINT_SEED: &[u8] = b"tape_mint";
-pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault";
\ No newline at end of file
+pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault";
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const REGISTRY_SEED: &[u8] = b"registry";
+
+// Objects are committed to as a Merkle tree over fixed-size chunks. A
+// challenged chunk travels with its sibling path in a SubmitProof or
+// AnswerChallenge, so both values are bounded by the transaction size limit.
+pub const CHUNK_SIZE: u64 = 64;
+// A proof carries one sibling per tree level. At depth 23 a single chunk and
+// its path still fit, with room left for compute budget instructions, so
+// objects stop at 2^23 chunks (512 MiB).
+pub const MAX_CHUNK_COUNT: u64 = 1 << 23;
+pub const CHALLENGES_PER_EPOCH: usize = 2;
+// Challenges are drawn from the hash of a slot CHALLENGE_DELAY_SLOTS past the
+// one an epoch opens in, which nobody knows at that point. SlotHashes keeps
+// only the last 512 slots, so the proof has to land within that many slots of
+// the seed slot; the default epoch duration is well inside it.
+pub const CHALLENGE_DELAY_SLOTS: u64 = 2;
+
+// Each object is stored by up to MAX_REPLICATION miners.
+pub const MAX_REPLICATION: usize = 4;
+
+// UpdateCommitment keeps this many replaced versions provable for the epochs
+// opened against them.
+pub const VERSION_HISTORY: usize = 2;
+
+// RegisterObject draws assignees from every registered miner, whose accounts
+// it takes all at once, so the registry is bounded by what one transaction
+// can carry with an address lookup table. Only miners holding the minimum
+// assignment stake are registered.
+pub const MAX_MINERS: usize = 32;
+
+pub const INITIAL_REPUTATION: u32 = 100;
+pub const MINER_METADATA_LEN: usize = 64;
+pub const MAX_DECAY_STEPS: i64 = 64;
+
+// Sensitive admin actions (Slash, SetAdmins, UpdateConfig) need approvals
+// from admin_threshold of the up to MAX_ADMINS keys in the admin set.
+pub const MAX_ADMINS: usize = 5;
+
+// Everything below is a default for the Config PDA, written by Initialize
+// and tuned per network through UpdateConfig; instructions read the account.
+
+// Miners are drawn by stake from those holding at least MIN_ASSIGNMENT_STAKE.
+// Owners pay PRICE_PER_CHUNK_EPOCH into the object escrow, per chunk per
+// epoch per replica.
+pub const MIN_ASSIGNMENT_STAKE: u64 = 1_000;
+pub const PRICE_PER_CHUNK_EPOCH: u64 = 10;
+
+// A proof is due EPOCH_DURATION_SECS after its epoch opens. Stake is locked
+// for UNSTAKE_COOLDOWN_SECS after every deposit.
+pub const EPOCH_DURATION_SECS: i64 = 120;
+pub const UNSTAKE_COOLDOWN_SECS: i64 = 3_600;
+
+// Emission schedule: every DECAY_PERIOD_SECS the per-epoch reward shrinks by
+// decay_numerator / decay_denom, starting from BASE_EPOCH_REWARD.
+pub const BASE_EPOCH_REWARD: u64 = 1_000_000;
+pub const DECAY_PERIOD_SECS: i64 = 86_400;
+
+// A submitted proof can be challenged for DISPUTE_WINDOW_SECS before the
+// epoch can be finalised. The challenger names a chunk the proof did not
+// cover, and the solver has DISPUTE_WINDOW_SECS to produce it; if it does
+// not, the challenge is upheld. Challengers post CHALLENGE_BOND; an upheld
+// challenge slashes SLASH_BPS basis points of the solver's stake and pays
+// CHALLENGER_SHARE_BPS of that to the challenger. Losing a challenge costs
+// CHALLENGE_REPUTATION_PENALTY on top of the SLASH_REPUTATION_PENALTY every
+// slash (by a challenge or the admin) costs.
+pub const DISPUTE_WINDOW_SECS: i64 = 3_600;
+pub const SLASH_BPS: u64 = 1_000;
+pub const CHALLENGE_BOND: u64 = 100_000;
+pub const CHALLENGER_SHARE_BPS: u64 = 5_000;
+pub const CHALLENGE_REPUTATION_PENALTY: u32 = 1;
+pub const SLASH_REPUTATION_PENALTY: u32 = 10;
+
+// Applied by MarkMissed to the assigned miner of an epoch with no proof by
+// its deadline.
+pub const MISSED_SLASH_BPS: u64 = 500;
+pub const MISSED_REPUTATION_PENALTY: u32 = 5;
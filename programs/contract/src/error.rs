@@ -0,0 +1,214 @@
+use pinocchio::program_error::ProgramError;
+
+/// Program errors, returned as `ProgramError::Custom(code)`. The codes are
+/// stable: new variants get new numbers and existing ones are never reused.
+/// Malformed account data, arithmetic overflow and missing accounts are still
+/// reported with the standard `ProgramError` variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractError {
+    LateSubmission = 1,
+    EpochUnderDispute = 2,
+    InvalidProof = 3,
+    CooldownActive = 4,
+    InvalidChunkCount = 5,
+    DisputeWindowOpen = 6,
+    InvalidReplicationFactor = 7,
+    NotEnoughMiners = 8,
+    MinerNotAssigned = 9,
+    RetentionExhausted = 10,
+    ObjectNotExpired = 11,
+    EpochNotComplete = 12,
+    PreviousVersionInUse = 13,
+    UnknownVersion = 14,
+
+    // Signers and authorities
+    MissingSigner = 15,
+    NotAdmin = 16,
+    NotObjectOwner = 17,
+    NotPendingOwner = 18,
+    NotMinerOrOperator = 19,
+
+    // Account addresses and relations
+    GlobalPdaMismatch = 20,
+    ObjectPdaMismatch = 21,
+    EpochPdaMismatch = 22,
+    MinerPdaMismatch = 23,
+    ChallengePdaMismatch = 24,
+    EscrowPdaMismatch = 25,
+    TreasuryPdaMismatch = 26,
+    EpochIdMismatch = 27,
+    MinerNotSolver = 28,
+    TokenAccountMismatch = 29,
+    RewardMintMismatch = 30,
+    RewardSourceMismatch = 31,
+    RentRecipientMismatch = 32,
+    AlreadyInitialized = 33,
+
+    // Epoch and challenge status
+    EpochNotOpen = 34,
+    EpochNotSubmitted = 35,
+    EpochNotChallenged = 36,
+    DeadlineNotReached = 37,
+    ChallengeResolved = 38,
+    EvidenceMatchesProof = 39,
+
+    // Miners and amounts
+    MinerNotRegistered = 40,
+    MinerNotEmpty = 41,
+    InsufficientStake = 42,
+    NoPendingRewards = 43,
+    NothingToRefund = 44,
+    ZeroAmount = 45,
+    InvalidCandidate = 46,
+    DuplicateCandidate = 47,
+    CandidateStakeTooLow = 48,
+
+    // Initialisation parameters
+    InvalidDecay = 49,
+    InvalidEmissionMode = 50,
+
+    // Governance
+    ProgramPaused = 51,
+    NotPendingAdmin = 52,
+    AdminQuorumNotMet = 53,
+    InvalidAdminSet = 54,
+    GlobalAccountMissing = 55,
+
+    // Config
+    ConfigPdaMismatch = 56,
+    InvalidConfig = 57,
+
+    // Account layouts
+    AccountDiscriminatorMismatch = 58,
+    AccountVersionMismatch = 59,
+    AlreadyMigrated = 60,
+
+    // Account validation
+    AccountOwnerMismatch = 61,
+    AccountNotWritable = 62,
+    ProgramIdMismatch = 63,
+
+    // Stake custody
+    StakeVaultMismatch = 64,
+    BondVaultMismatch = 65,
+
+    // Miner lifecycle
+    MinerHasOpenEpochs = 66,
+    MinerPenalised = 67,
+
+    // Miner registry
+    RegistryPdaMismatch = 68,
+    RegistryFull = 69,
+
+    // Epoch schedule
+    EpochNotDue = 70,
+    ReplicaNotOverdue = 71,
+
+    // Proofs
+    UnsupportedProofType = 72,
+    ChallengeSeedPending = 73,
+    ChallengeSeedExpired = 74,
+
+    // Miner lifecycle
+    MinerHasAssignments = 75,
+
+    // Challenges
+    ChunkAlreadyProven = 76,
+    InvalidChunkIndex = 77,
+    DisputeWindowClosed = 78,
+}
+
+impl ContractError {
+    /// Decodes a `Custom` error code, for clients and the indexer.
+    pub fn from_code(code: u32) -> Option<Self> {
+        use ContractError::*;
+        let error = match code {
+            1 => LateSubmission,
+            2 => EpochUnderDispute,
+            3 => InvalidProof,
+            4 => CooldownActive,
+            5 => InvalidChunkCount,
+            6 => DisputeWindowOpen,
+            7 => InvalidReplicationFactor,
+            8 => NotEnoughMiners,
+            9 => MinerNotAssigned,
+            10 => RetentionExhausted,
+            11 => ObjectNotExpired,
+            12 => EpochNotComplete,
+            13 => PreviousVersionInUse,
+            14 => UnknownVersion,
+            15 => MissingSigner,
+            16 => NotAdmin,
+            17 => NotObjectOwner,
+            18 => NotPendingOwner,
+            19 => NotMinerOrOperator,
+            20 => GlobalPdaMismatch,
+            21 => ObjectPdaMismatch,
+            22 => EpochPdaMismatch,
+            23 => MinerPdaMismatch,
+            24 => ChallengePdaMismatch,
+            25 => EscrowPdaMismatch,
+            26 => TreasuryPdaMismatch,
+            27 => EpochIdMismatch,
+            28 => MinerNotSolver,
+            29 => TokenAccountMismatch,
+            30 => RewardMintMismatch,
+            31 => RewardSourceMismatch,
+            32 => RentRecipientMismatch,
+            33 => AlreadyInitialized,
+            34 => EpochNotOpen,
+            35 => EpochNotSubmitted,
+            36 => EpochNotChallenged,
+            37 => DeadlineNotReached,
+            38 => ChallengeResolved,
+            39 => EvidenceMatchesProof,
+            40 => MinerNotRegistered,
+            41 => MinerNotEmpty,
+            42 => InsufficientStake,
+            43 => NoPendingRewards,
+            44 => NothingToRefund,
+            45 => ZeroAmount,
+            46 => InvalidCandidate,
+            47 => DuplicateCandidate,
+            48 => CandidateStakeTooLow,
+            49 => InvalidDecay,
+            50 => InvalidEmissionMode,
+            51 => ProgramPaused,
+            52 => NotPendingAdmin,
+            53 => AdminQuorumNotMet,
+            54 => InvalidAdminSet,
+            55 => GlobalAccountMissing,
+            56 => ConfigPdaMismatch,
+            57 => InvalidConfig,
+            58 => AccountDiscriminatorMismatch,
+            59 => AccountVersionMismatch,
+            60 => AlreadyMigrated,
+            61 => AccountOwnerMismatch,
+            62 => AccountNotWritable,
+            63 => ProgramIdMismatch,
+            64 => StakeVaultMismatch,
+            65 => BondVaultMismatch,
+            66 => MinerHasOpenEpochs,
+            67 => MinerPenalised,
+            68 => RegistryPdaMismatch,
+            69 => RegistryFull,
+            70 => EpochNotDue,
+            71 => ReplicaNotOverdue,
+            72 => UnsupportedProofType,
+            73 => ChallengeSeedPending,
+            74 => ChallengeSeedExpired,
+            75 => MinerHasAssignments,
+            76 => ChunkAlreadyProven,
+            77 => InvalidChunkIndex,
+            78 => DisputeWindowClosed,
+            _ => return None,
+        };
+        Some(error)
+    }
+}
+
+impl From<ContractError> for ProgramError {
+    fn from(e: ContractError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
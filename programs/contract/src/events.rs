@@ -0,0 +1,219 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use pinocchio::{log::sol_log_data, pubkey::Pubkey};
+
+use crate::state::ConfigParams;
+
+/// Events logged by every instruction through `sol_log_data`, Borsh-encoded.
+/// Variants are only ever appended so indexers keep decoding old logs.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum ContractEvent {
+    Initialized {
+        admin: Pubkey,
+        reward_mint: Pubkey,
+        emission_mode: u8,
+        emission_cap: u64,
+        timestamp: i64,
+    },
+    ObjectRegistered {
+        object_id: u128,
+        owner: Pubkey,
+        commitment: [u8; 32],
+        size: u64,
+        chunk_count: u64,
+        retention_epochs: u64,
+        assigned_miners: Vec<Pubkey>,
+        deposit: u64,
+        timestamp: i64,
+    },
+    EpochCreated {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        seed_slot: u64,
+        deadline_ts: i64,
+        reward: u64,
+        payment: u64,
+        version: u32,
+        timestamp: i64,
+    },
+    ProofSubmitted {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        nonce: u64,
+        proof_hash: [u8; 32],
+        timestamp: i64,
+    },
+    EpochChallenged {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        challenger: Pubkey,
+        chunk_index: u64,
+        bond: u64,
+        timestamp: i64,
+    },
+    ChallengeResolved {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        challenger: Pubkey,
+        upheld: bool,
+        slashed: u64,
+        challenger_share: u64,
+        reward: u64,
+        timestamp: i64,
+    },
+    EpochFinalized {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        reward: u64,
+        payment: u64,
+        timestamp: i64,
+    },
+    EpochMissed {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        slashed: u64,
+        timestamp: i64,
+    },
+    MinerRegistered {
+        miner: Pubkey,
+        operator: Pubkey,
+        timestamp: i64,
+    },
+    MinerDeregistered {
+        miner: Pubkey,
+        timestamp: i64,
+    },
+    Staked {
+        miner: Pubkey,
+        amount: u64,
+        stake: u64,
+        timestamp: i64,
+    },
+    Unstaked {
+        miner: Pubkey,
+        amount: u64,
+        stake: u64,
+        timestamp: i64,
+    },
+    MinerSlashed {
+        miner: Pubkey,
+        amount: u64,
+        stake: u64,
+        timestamp: i64,
+    },
+    RewardsClaimed {
+        miner: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    EscrowRefunded {
+        object_id: u128,
+        owner: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    ObjectClosed {
+        object_id: u128,
+        owner: Pubkey,
+        refunded: u64,
+        timestamp: i64,
+    },
+    EpochClosed {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        timestamp: i64,
+    },
+    RetentionExtended {
+        object_id: u128,
+        owner: Pubkey,
+        retention_epochs: u64,
+        cost: u64,
+        timestamp: i64,
+    },
+    CommitmentUpdated {
+        object_id: u128,
+        version: u32,
+        commitment: [u8; 32],
+        top_up: u64,
+        freed: u64,
+        timestamp: i64,
+    },
+    ObjectTransferProposed {
+        object_id: u128,
+        owner: Pubkey,
+        pending_owner: Pubkey,
+        timestamp: i64,
+    },
+    ObjectTransferred {
+        object_id: u128,
+        from: Pubkey,
+        to: Pubkey,
+        timestamp: i64,
+    },
+    AdminProposed {
+        admin: Pubkey,
+        pending_admin: Pubkey,
+        timestamp: i64,
+    },
+    AdminChanged {
+        from: Pubkey,
+        to: Pubkey,
+        timestamp: i64,
+    },
+    PauseUpdated {
+        paused: u64,
+        timestamp: i64,
+    },
+    AdminSetUpdated {
+        admins: Vec<Pubkey>,
+        threshold: u8,
+        timestamp: i64,
+    },
+    ConfigUpdated {
+        params: ConfigParams,
+        timestamp: i64,
+    },
+    AccountMigrated {
+        account: Pubkey,
+        version: u8,
+        timestamp: i64,
+    },
+    ReplicaReleased {
+        object_id: u128,
+        miner: Pubkey,
+        refunded: u64,
+        timestamp: i64,
+    },
+    StakeMigrated {
+        miner: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    ChallengeAnswered {
+        object_id: u128,
+        epoch_id: u128,
+        miner: Pubkey,
+        chunk_index: u64,
+        timestamp: i64,
+    },
+}
+
+impl ContractEvent {
+    /// Logs the event as a single `Program data:` entry.
+    pub fn emit(&self) {
+        if let Ok(data) = borsh::to_vec(self) {
+            sol_log_data(&[&data]);
+        }
+    }
+
+    /// Decodes the bytes of a `Program data:` entry (after base64 decoding).
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        Self::try_from_slice(data).ok()
+    }
+}
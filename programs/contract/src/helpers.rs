@@ -1,5 +1,10 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
-use crate::constants::{EPOCH_SEED, GLOBAL_SEED, MINER_SEED, OBJECT_SEED, REWARD_VAULT_SEED, TAPE_MINT_SEED};
+use pinocchio::{account_info::AccountInfo, instruction::{Seed, Signer}, msg, program_error::ProgramError, pubkey::Pubkey, seeds, sysvars::{rent::Rent, slot_hashes::SlotHashes, Sysvar}, ProgramResult};
+use pinocchio_system::instructions::{Allocate, Assign, CreateAccount, Transfer};
+use solana_sha256_hasher::hashv;
+use pinocchio_token::state::TokenAccount;
+use crate::error::ContractError;
+use crate::state::{Config, ConfigParams, GlobalState, MinerRegistry, ProgramAccount, ZeroCopy};
+use crate::constants::{BOND_VAULT_SEED, CHALLENGE_SEED, CONFIG_SEED, EPOCH_SEED, ESCROW_SEED, GLOBAL_SEED, MAX_MINERS, MAX_REPLICATION, MINER_SEED, OBJECT_SEED, REGISTRY_SEED, REWARD_VAULT_SEED, STAKE_VAULT_SEED, TAPE_MINT_SEED, TREASURY_SEED};
 use pinocchio::pubkey::find_program_address;
 
 pub fn find_pda(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
@@ -10,21 +15,57 @@ pub fn global_pda(program_id: &Pubkey) -> (Pubkey, u8) {
     find_pda(&[GLOBAL_SEED], program_id)
 }
 
-pub fn object_pda(program_id: &Pubkey, owner: &Pubkey, object_id: u128) -> (Pubkey, u8) {
+pub fn config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    find_pda(&[CONFIG_SEED], program_id)
+}
+
+/// Object PDAs are seeded by the registering owner (`ObjectRecord.creator`),
+/// so the address stays the same when ownership is transferred.
+pub fn object_pda(program_id: &Pubkey, creator: &Pubkey, object_id: u128) -> (Pubkey, u8) {
     let id_bytes = object_id.to_le_bytes();
-    find_pda(&[OBJECT_SEED, owner.as_ref(), &id_bytes], program_id)
+    find_pda(&[OBJECT_SEED, creator.as_ref(), &id_bytes], program_id)
 }
 
-pub fn epoch_pda(program_id: &Pubkey, object_id: u128, epoch_id: u128) -> (Pubkey, u8) {
-    let oid_bytes = object_id.to_le_bytes();
+/// Epochs are seeded by the object account rather than its id, which any
+/// creator can reuse.
+pub fn epoch_pda(program_id: &Pubkey, object: &Pubkey, miner: &Pubkey, epoch_id: u128) -> (Pubkey, u8) {
     let eid_bytes = epoch_id.to_le_bytes();
-    find_pda(&[EPOCH_SEED, &oid_bytes, &eid_bytes], program_id)
+    find_pda(&[EPOCH_SEED, object.as_ref(), miner.as_ref(), &eid_bytes], program_id)
 }
 
 pub fn miner_pda(program_id: &Pubkey, miner: &Pubkey) -> (Pubkey, u8) {
     find_pda(&[MINER_SEED, miner.as_ref()], program_id)
 }
 
+pub fn challenge_pda(program_id: &Pubkey, object: &Pubkey, miner: &Pubkey, epoch_id: u128) -> (Pubkey, u8) {
+    let eid_bytes = epoch_id.to_le_bytes();
+    find_pda(&[CHALLENGE_SEED, object.as_ref(), miner.as_ref(), &eid_bytes], program_id)
+}
+
+pub fn escrow_pda(program_id: &Pubkey, object: &Pubkey) -> (Pubkey, u8) {
+    find_pda(&[ESCROW_SEED, object.as_ref()], program_id)
+}
+
+/// Token account holding one miner's stake, created by Stake and held by
+/// the global PDA.
+pub fn stake_vault_pda(program_id: &Pubkey, miner: &Pubkey) -> (Pubkey, u8) {
+    find_pda(&[STAKE_VAULT_SEED, miner.as_ref()], program_id)
+}
+
+/// Token account holding every pending challenge bond, created by the first
+/// ChallengeProof and held by the global PDA.
+pub fn bond_vault_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    find_pda(&[BOND_VAULT_SEED], program_id)
+}
+
+pub fn registry_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    find_pda(&[REGISTRY_SEED], program_id)
+}
+
+pub fn treasury_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    find_pda(&[TREASURY_SEED], program_id)
+}
+
 pub fn tape_mint_pda(program_id: &Pubkey) -> (Pubkey, u8) {
     find_pda(&[TAPE_MINT_SEED], program_id)
 }
@@ -37,4 +78,372 @@ pub fn next_account<'a>(
     iter: &mut impl Iterator<Item = &'a AccountInfo>,
 ) -> Result<&'a AccountInfo, ProgramError> {
     iter.next().ok_or(ProgramError::NotEnoughAccountKeys)
-}
\ No newline at end of file
+}
+
+/// Checks an account holding program state: it must be owned by this
+/// program, and writable when the instruction updates it. Address checks
+/// stay with the caller, since most PDAs derive from the account's contents.
+pub fn check_program_account(program_id: &Pubkey, account: &AccountInfo, writable: bool) -> ProgramResult {
+    if !account.is_owned_by(program_id) {
+        msg!("Account not owned by program");
+        return Err(ContractError::AccountOwnerMismatch.into());
+    }
+    if writable {
+        check_writable(account)?;
+    }
+    Ok(())
+}
+
+pub fn check_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable() {
+        msg!("Account must be writable");
+        return Err(ContractError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// Checks that a program passed for a CPI is the expected one.
+pub fn check_program_id(account: &AccountInfo, expected: &Pubkey) -> ProgramResult {
+    if account.key() != expected {
+        msg!("Unexpected program account");
+        return Err(ContractError::ProgramIdMismatch.into());
+    }
+    Ok(())
+}
+
+/// Reads the protocol parameters from the Config PDA.
+pub fn load_config(program_id: &Pubkey, config_account: &AccountInfo) -> Result<ConfigParams, ProgramError> {
+    let (expected_config, _) = config_pda(program_id);
+    if expected_config != *config_account.key() {
+        msg!("Config PDA mismatch");
+        return Err(ContractError::ConfigPdaMismatch.into());
+    }
+    check_program_account(program_id, config_account, false)?;
+
+    let config_data = config_account.try_borrow_data()?;
+    let config = Config::load(&config_data)?;
+    Ok(config.params)
+}
+
+/// Checks that `account` is a token account of `mint` held by `owner`.
+pub fn check_token_account(account: &AccountInfo, owner: &Pubkey, mint: &Pubkey) -> ProgramResult {
+    let token = TokenAccount::from_account_info(account)?;
+    if token.owner() != owner || token.mint() != mint {
+        msg!("Token account mismatch");
+        return Err(ContractError::TokenAccountMismatch.into());
+    }
+    Ok(())
+}
+
+/// Checks that `account` is the stake vault of `miner`. The address can only
+/// have been created by the program, so it needs no further token checks.
+pub fn check_stake_vault(program_id: &Pubkey, account: &AccountInfo, miner: &Pubkey) -> ProgramResult {
+    let (expected_vault, _) = stake_vault_pda(program_id, miner);
+    if expected_vault != *account.key() {
+        msg!("Stake vault mismatch");
+        return Err(ContractError::StakeVaultMismatch.into());
+    }
+    Ok(())
+}
+
+/// Checks that `account` is the challenge bond vault.
+pub fn check_bond_vault(program_id: &Pubkey, account: &AccountInfo) -> ProgramResult {
+    let (expected_vault, _) = bond_vault_pda(program_id);
+    if expected_vault != *account.key() {
+        msg!("Bond vault mismatch");
+        return Err(ContractError::BondVaultMismatch.into());
+    }
+    Ok(())
+}
+
+/// Checks that `account` is the writable miner registry.
+pub fn check_registry(program_id: &Pubkey, account: &AccountInfo) -> ProgramResult {
+    let (expected_registry, _) = registry_pda(program_id);
+    if expected_registry != *account.key() {
+        msg!("Registry PDA mismatch");
+        return Err(ContractError::RegistryPdaMismatch.into());
+    }
+    check_program_account(program_id, account, true)
+}
+
+/// Adds `miner` to the registry, which the first registration creates.
+pub fn join_registry(program_id: &Pubkey, payer: &AccountInfo, registry_account: &AccountInfo, miner: &Pubkey) -> ProgramResult {
+    let (expected_registry, bump) = registry_pda(program_id);
+    if expected_registry != *registry_account.key() {
+        msg!("Registry PDA mismatch");
+        return Err(ContractError::RegistryPdaMismatch.into());
+    }
+    if registry_account.data_is_empty() {
+        let bump_arr = [bump];
+        let seeds = seeds!(REGISTRY_SEED, &bump_arr);
+        create_pda_account(payer, registry_account, MinerRegistry::SPACE, program_id, &seeds)?;
+        let registry = MinerRegistry {
+            miners: [Pubkey::default(); MAX_MINERS],
+            count: 0,
+            draws: 0.into(),
+            bump,
+        };
+        registry.store(&mut registry_account.try_borrow_mut_data()?)?;
+    }
+    check_program_account(program_id, registry_account, true)?;
+
+    let mut registry_data = registry_account.try_borrow_mut_data()?;
+    MinerRegistry::load_mut(&mut registry_data)?.insert(miner)
+}
+
+/// Removes `miner` from the registry, if the registry has been created.
+pub fn leave_registry(program_id: &Pubkey, registry_account: &AccountInfo, miner: &Pubkey) -> ProgramResult {
+    let (expected_registry, _) = registry_pda(program_id);
+    if expected_registry != *registry_account.key() {
+        msg!("Registry PDA mismatch");
+        return Err(ContractError::RegistryPdaMismatch.into());
+    }
+    if registry_account.data_is_empty() {
+        return Ok(());
+    }
+    check_program_account(program_id, registry_account, true)?;
+
+    let mut registry_data = registry_account.try_borrow_mut_data()?;
+    MinerRegistry::load_mut(&mut registry_data)?.remove(miner);
+    Ok(())
+}
+
+/// Moves tokens out of a program vault, signed by the global PDA. The global
+/// account must not be borrowed by the caller.
+pub fn global_transfer(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    global_account: &AccountInfo,
+    global_bump: u8,
+    amount: u64,
+) -> ProgramResult {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let bump_arr = [global_bump];
+    let seeds = seeds!(GLOBAL_SEED, &bump_arr);
+    pinocchio_token::instructions::Transfer {
+        from,
+        to,
+        authority: global_account,
+        amount,
+    }.invoke_signed(&[Signer::from(&seeds)])
+}
+
+/// Closes a token account held by the global PDA, sending its rent to
+/// `destination`. The account must be empty.
+pub fn global_close(
+    account: &AccountInfo,
+    destination: &AccountInfo,
+    global_account: &AccountInfo,
+    global_bump: u8,
+) -> ProgramResult {
+    let bump_arr = [global_bump];
+    let seeds = seeds!(GLOBAL_SEED, &bump_arr);
+    pinocchio_token::instructions::CloseAccount {
+        account,
+        destination,
+        authority: global_account,
+    }.invoke_signed(&[Signer::from(&seeds)])
+}
+
+/// Creates the stake vault of `miner` if it does not exist yet: a token
+/// account of the reward mint at its PDA, held by the global PDA.
+pub fn init_stake_vault(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    stake_vault: &AccountInfo,
+    miner: &Pubkey,
+    reward_mint_account: &AccountInfo,
+    global_pda: &Pubkey,
+) -> ProgramResult {
+    let (expected_vault, vault_bump) = stake_vault_pda(program_id, miner);
+    if expected_vault != *stake_vault.key() {
+        msg!("Stake vault mismatch");
+        return Err(ContractError::StakeVaultMismatch.into());
+    }
+    if !stake_vault.data_is_empty() {
+        return Ok(());
+    }
+
+    let bump_arr = [vault_bump];
+    let seeds = seeds!(STAKE_VAULT_SEED, miner.as_ref(), &bump_arr);
+    create_pda_account(payer, stake_vault, TokenAccount::LEN, &pinocchio_token::ID, &seeds)?;
+    pinocchio_token::instructions::InitializeAccount3 {
+        account: stake_vault,
+        mint: reward_mint_account,
+        owner: global_pda,
+    }.invoke()
+}
+
+/// Fails if `bit` is set in the global pause mask. The global account is
+/// found among `accounts`, so instructions that do not otherwise use it take
+/// it as a trailing account.
+pub fn check_not_paused(program_id: &Pubkey, accounts: &[AccountInfo], bit: u32) -> ProgramResult {
+    let (global_pda, _) = global_pda(program_id);
+    let global_account = accounts.iter().find(|a| *a.key() == global_pda).ok_or_else(|| {
+        msg!("Global account missing");
+        ProgramError::from(ContractError::GlobalAccountMissing)
+    })?;
+    check_program_account(program_id, global_account, false)?;
+
+    let global_data = global_account.try_borrow_data()?;
+    let global_state = GlobalState::load(&global_data)?;
+    if global_state.is_paused(bit) {
+        msg!("Instruction is paused");
+        return Err(ContractError::ProgramPaused.into());
+    }
+    Ok(())
+}
+
+/// Checks that enough distinct members of the admin set signed among
+/// `approvers`. Non-members and repeated keys are not counted.
+pub fn check_admin_quorum<'a>(
+    global_state: &GlobalState,
+    approvers: impl Iterator<Item = &'a AccountInfo>,
+) -> ProgramResult {
+    let mut approved = 0u32; // bit per admin slot
+    for approver in approvers {
+        if !approver.is_signer() {
+            continue;
+        }
+        if let Some(slot) = global_state.admin_slot(approver.key()) {
+            approved |= 1 << slot;
+        }
+    }
+    if approved.count_ones() < global_state.admin_threshold as u32 {
+        msg!("Not enough admin approvals");
+        return Err(ContractError::AdminQuorumNotMet.into());
+    }
+    Ok(())
+}
+
+/// Hash of the most recent entry of the SlotHashes sysvar, which the caller
+/// of an instruction cannot choose.
+pub fn recent_slot_hash(slot_hashes_account: &AccountInfo) -> Result<[u8; 32], ProgramError> {
+    let slot_hashes = SlotHashes::from_account_info(slot_hashes_account)?;
+    let recent = slot_hashes.get_entry(0).ok_or(ProgramError::UnsupportedSysvar)?;
+    Ok(recent.hash)
+}
+
+/// Hash of the first slot at or after `seed_slot` in the SlotHashes sysvar.
+/// Skipped slots have no entry, so the next produced slot stands in for them.
+pub fn seed_slot_hash(slot_hashes_account: &AccountInfo, seed_slot: u64) -> Result<[u8; 32], ProgramError> {
+    let slot_hashes = SlotHashes::from_account_info(slot_hashes_account)?;
+    // Entries run from the newest slot to the oldest.
+    let entries = slot_hashes.entries();
+    let (newest, oldest) = match (entries.first(), entries.last()) {
+        (Some(newest), Some(oldest)) => (newest, oldest),
+        _ => return Err(ProgramError::UnsupportedSysvar),
+    };
+    if newest.slot() < seed_slot {
+        msg!("Challenge seed slot not reached yet");
+        return Err(ContractError::ChallengeSeedPending.into());
+    }
+    if oldest.slot() > seed_slot {
+        msg!("Challenge seed slot no longer in SlotHashes");
+        return Err(ContractError::ChallengeSeedExpired.into());
+    }
+    let entry = entries.iter().rev().find(|entry| entry.slot() >= seed_slot).ok_or(ProgramError::UnsupportedSysvar)?;
+    Ok(entry.hash)
+}
+
+/// Epoch nonce mixed from the hash of the epoch's seed slot, which did not
+/// exist when the epoch was opened, so neither its opener nor its miner can
+/// pick the challenge.
+pub fn epoch_nonce(seed: &[u8; 32], object: &Pubkey, miner: &Pubkey, epoch_id: u128) -> u64 {
+    let digest = hashv(&[seed, object.as_ref(), miner.as_ref(), &epoch_id.to_le_bytes()]).to_bytes();
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(word)
+}
+
+/// Stake-weighted sampling without replacement: each draw picks one of the
+/// remaining candidates with probability proportional to its stake. Callers
+/// ensure there are at least `count` candidates, all with non-zero stake.
+pub fn select_miners(
+    candidates: &mut Vec<(Pubkey, u64)>,
+    seed: &[u8; 32],
+    count: usize,
+) -> [Pubkey; MAX_REPLICATION] {
+    let mut selected = [Pubkey::default(); MAX_REPLICATION];
+    for (round, slot) in selected.iter_mut().take(count).enumerate() {
+        let total: u128 = candidates.iter().map(|(_, stake)| *stake as u128).sum();
+        let digest = hashv(&[seed, &[round as u8]]).to_bytes();
+        let mut word = [0u8; 16];
+        word.copy_from_slice(&digest[..16]);
+        let mut target = u128::from_le_bytes(word) % total;
+
+        let mut pos = candidates.len() - 1;
+        for (i, (_, stake)) in candidates.iter().enumerate() {
+            if target < *stake as u128 {
+                pos = i;
+                break;
+            }
+            target -= *stake as u128;
+        }
+        *slot = candidates.swap_remove(pos).0;
+    }
+    selected
+}
+
+/// Creates a rent-exempt, program-owned account at a PDA, rejecting accounts
+/// that have already been initialised.
+pub fn create_pda_account(
+    payer: &AccountInfo,
+    account: &AccountInfo,
+    space: usize,
+    program_id: &Pubkey,
+    seeds: &[Seed],
+) -> ProgramResult {
+    check_writable(account)?;
+    if !account.data_is_empty() || account.is_owned_by(program_id) {
+        msg!("Account already initialised");
+        return Err(ContractError::AlreadyInitialized.into());
+    }
+
+    let lamports = Rent::get()?.minimum_balance(space);
+    let current = account.lamports();
+
+    if current == 0 {
+        return CreateAccount {
+            from: payer,
+            to: account,
+            lamports,
+            space: space as u64,
+            owner: program_id,
+        }.invoke_signed(&[Signer::from(seeds)]);
+    }
+
+    // The address was pre-funded (CreateAccount would fail), so top it up and
+    // claim it instead.
+    if lamports > current {
+        Transfer {
+            from: payer,
+            to: account,
+            lamports: lamports - current,
+        }.invoke()?;
+    }
+    Allocate {
+        account,
+        space: space as u64,
+    }.invoke_signed(&[Signer::from(seeds)])?;
+    Assign {
+        account,
+        owner: program_id,
+    }.invoke_signed(&[Signer::from(seeds)])
+}
+
+/// Closes a program-owned account, sending its lamports to `destination`.
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    check_writable(destination)?;
+    {
+        let mut dest_lamports = destination.try_borrow_mut_lamports()?;
+        *dest_lamports = dest_lamports
+            .checked_add(account.lamports())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+    *account.try_borrow_mut_lamports()? = 0;
+    account.close()
+}
@@ -0,0 +1,59 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, global_pda, next_account}, state::{GlobalState, ZeroCopy}};
+
+pub fn accept_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let new_admin = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+
+    if !new_admin.is_signer() {
+        msg!("New admin must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    let (expected_global, _) = global_pda(program_id);
+    if expected_global != *global_account.key() {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, true)?;
+
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+
+    if global_state.pending_admin.get() != Some(*new_admin.key()) {
+        msg!("Signer is not the pending admin");
+        return Err(ContractError::NotPendingAdmin.into());
+    }
+
+    // A rotated-out key must not keep its vote in the admin set, so the new
+    // admin takes over its seat there too. Other seats only change through
+    // SetAdmins.
+    let previous = global_state.admin;
+    let in_set = global_state.admin_slot(&previous).is_some();
+    if in_set && !global_state.replace_admin(&previous, new_admin.key()) {
+        msg!("Admin set would fall below its threshold");
+        return Err(ContractError::InvalidAdminSet.into());
+    }
+    global_state.admin = *new_admin.key();
+    global_state.pending_admin.set(None);
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    ContractEvent::AdminChanged {
+        from: previous,
+        to: *new_admin.key(),
+        timestamp,
+    }.emit();
+    if in_set {
+        ContractEvent::AdminSetUpdated {
+            admins: global_state.admins[..global_state.admin_count as usize].to_vec(),
+            threshold: global_state.admin_threshold,
+            timestamp,
+        }.emit();
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, next_account, object_pda}, state::{ObjectRecord, ZeroCopy}};
+
+pub fn accept_object(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    object_id: u128,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let new_owner = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+
+    if !new_owner.is_signer() {
+        msg!("New owner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    check_program_account(program_id, object_account, true)?;
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || object.object_id.get() != object_id {
+        msg!("Object PDA mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    if object.pending_owner.get() != Some(*new_owner.key()) {
+        msg!("Signer is not the pending owner");
+        return Err(ContractError::NotPendingOwner.into());
+    }
+
+    let previous = object.owner;
+    object.owner = *new_owner.key();
+    object.pending_owner.set(None);
+
+    ContractEvent::ObjectTransferred {
+        object_id,
+        from: previous,
+        to: *new_owner.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{challenge_pda, check_program_account, epoch_pda, load_config, miner_pda, next_account, object_pda}, merkle, state::{ChallengeRecord, ChallengeStatus, ChunkProof, EpochRecord, EpochStatus, MinerAccount, ObjectRecord, ProgramAccount, ZeroCopy}};
+
+/// The solver produces the challenged chunk within the dispute window. It
+/// only touches the accounts needed to check the chunk, so a path as deep as
+/// SubmitProof accepts still fits in the transaction; ResolveChallenge then
+/// rejects the challenge.
+pub fn answer_challenge(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    epoch_id: u128,
+    proof: ChunkProof,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let caller = next_account(accounts_iter)?;
+    let epoch_account = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let challenge_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+
+    if !caller.is_signer() {
+        msg!("Caller must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    let config = load_config(program_id, config_account)?;
+    check_program_account(program_id, epoch_account, false)?;
+    check_program_account(program_id, object_account, false)?;
+    check_program_account(program_id, miner_account, false)?;
+    check_program_account(program_id, challenge_account, true)?;
+
+    let epoch_data = epoch_account.try_borrow_data()?;
+    let epoch = EpochRecord::load(&epoch_data)?;
+
+    if epoch.epoch_id.get() != epoch_id {
+        msg!("Epoch mismatch");
+        return Err(ContractError::EpochIdMismatch.into());
+    }
+
+    let (expected_epoch, _) = epoch_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_epoch != *epoch_account.key() {
+        msg!("Epoch PDA mismatch");
+        return Err(ContractError::EpochPdaMismatch.into());
+    }
+
+    if epoch.status()? != EpochStatus::Challenged {
+        msg!("Epoch not under dispute");
+        return Err(ContractError::EpochNotChallenged.into());
+    }
+
+    let object_data = object_account.try_borrow_data()?;
+    let object = ObjectRecord::load(&object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || *object_account.key() != epoch.object {
+        msg!("Object account mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    let solver = epoch.solver.get().ok_or(ContractError::EpochNotSubmitted)?;
+    let (expected_miner, _) = miner_pda(program_id, &solver);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account does not match epoch solver");
+        return Err(ContractError::MinerNotSolver.into());
+    }
+
+    let miner_data = miner_account.try_borrow_data()?;
+    let miner_acc = MinerAccount::load(&miner_data)?;
+    if *caller.key() != solver && *caller.key() != miner_acc.operator {
+        msg!("Only the solver or its operator can answer a challenge");
+        return Err(ContractError::NotMinerOrOperator.into());
+    }
+
+    let (expected_challenge, _) = challenge_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_challenge != *challenge_account.key() {
+        msg!("Challenge PDA mismatch");
+        return Err(ContractError::ChallengePdaMismatch.into());
+    }
+
+    let mut challenge_data = challenge_account.try_borrow_mut_data()?;
+    let mut challenge = ChallengeRecord::load(&challenge_data)?;
+
+    if challenge.status != ChallengeStatus::Pending {
+        msg!("Challenge already answered or resolved");
+        return Err(ContractError::ChallengeResolved.into());
+    }
+
+    let clock = Clock::get()?;
+    if clock.unix_timestamp >= challenge.created_at.saturating_add(config.dispute_window_secs) {
+        msg!("Dispute window closed");
+        return Err(ContractError::DisputeWindowClosed.into());
+    }
+
+    let (commitment, size, chunk_count) = object.content(epoch.version.get()).ok_or_else(|| {
+        msg!("Epoch version no longer retained");
+        ProgramError::from(ContractError::UnknownVersion)
+    })?;
+    if !merkle::verify_chunk(&commitment, size, chunk_count, challenge.chunk_index, &proof) {
+        msg!("Invalid challenge answer");
+        return Err(ContractError::InvalidProof.into());
+    }
+
+    challenge.status = ChallengeStatus::Answered;
+    challenge.store(&mut challenge_data)?;
+
+    ContractEvent::ChallengeAnswered {
+        object_id: epoch.object_id.get(),
+        epoch_id,
+        miner: epoch.miner,
+        chunk_index: challenge.chunk_index,
+        timestamp: clock.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -1,57 +1,162 @@
-use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
-use borsh::{BorshDeserialize, BorshSerialize};
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, seeds, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::{instructions::{InitializeAccount3, Transfer}, state::TokenAccount};
 
-use crate::{helpers::next_account, state::{EpochRecord, EpochStatus, MinerAccount}};
+use crate::{events::ContractEvent, error::ContractError, constants::{BOND_VAULT_SEED, CHALLENGE_SEED}, helpers::{bond_vault_pda, challenge_pda, check_program_account, check_program_id, create_pda_account, epoch_pda, global_pda, load_config, next_account, object_pda}, state::{ChallengeRecord, ChallengeStatus, EpochRecord, EpochStatus, GlobalState, ObjectRecord, ProgramAccount, ZeroCopy}};
 
 pub fn challenge_proof(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     epoch_id: u128,
-    evidence_hash: [u8; 32],
+    chunk_index: u64,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let challenger = next_account(accounts_iter)?;
     let epoch_account = next_account(accounts_iter)?;
-    let miner_account = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let challenge_account = next_account(accounts_iter)?;
+    let challenger_token_account = next_account(accounts_iter)?;
+    let bond_vault = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+    let reward_mint_account = next_account(accounts_iter)?;
 
     if !challenger.is_signer() {
         msg!("Challenger must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    let config = load_config(program_id, config_account)?;
+    check_program_account(program_id, epoch_account, true)?;
+    check_program_account(program_id, object_account, false)?;
+    check_program_account(program_id, global_account, false)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
+    check_program_id(system_program, &pinocchio_system::ID)?;
 
     let mut epoch_data = epoch_account.try_borrow_mut_data()?;
-    let mut epoch: EpochRecord = EpochRecord::try_from_slice(&epoch_data).map_err(|_| {
-        msg!("Failed to deserialize epoch record");
-        ProgramError::InvalidAccountData
-    })?;
+    let epoch = EpochRecord::load_mut(&mut epoch_data)?;
 
-    if epoch.epoch_id != epoch_id {
+    if epoch.epoch_id.get() != epoch_id {
         msg!("Epoch mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::EpochIdMismatch.into());
     }
 
-    if epoch.status != EpochStatus::Submitted {
+    let (expected_epoch, _) = epoch_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_epoch != *epoch_account.key() {
+        msg!("Epoch PDA mismatch");
+        return Err(ContractError::EpochPdaMismatch.into());
+    }
+
+    if epoch.status()? != EpochStatus::Submitted {
         msg!("Epoch not in submitted state");
-        return Err(ProgramError::InvalidArgument);
-    }
-
-    epoch.status = EpochStatus::Challenged;
-    epoch.serialize(&mut &mut epoch_data[..]).map_err(|_| {
-        msg!("Failed to serialize epoch record");
-        ProgramError::InvalidAccountData
-    })?;
-
-    let mut miner_data = miner_account.try_borrow_mut_data()?;
-    let mut miner_acc = MinerAccount::try_from_slice(&miner_data).map_err(|_| {
-        msg!("Failed to deserialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
-    miner_acc.reputation = miner_acc.reputation.saturating_sub(1);
-    miner_acc.serialize(&mut &mut miner_data[..]).map_err(|_| {
-        msg!("Failed to serialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
-
-    msg!(&format!("EVENT:EpochChallenged:{}", epoch_id));
+        return Err(ContractError::EpochNotSubmitted.into());
+    }
+
+    // SubmitProof already verified the chunks it proved, so a challenge asks
+    // the solver for one it did not: a miner that only kept those cannot
+    // answer.
+    {
+        let object_data = object_account.try_borrow_data()?;
+        let object = ObjectRecord::load(&object_data)?;
+
+        let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+        if expected_object != *object_account.key() || *object_account.key() != epoch.object {
+            msg!("Object account mismatch");
+            return Err(ContractError::ObjectPdaMismatch.into());
+        }
+
+        let (_, _, chunk_count) = object.content(epoch.version.get()).ok_or_else(|| {
+            msg!("Epoch version no longer retained");
+            ProgramError::from(ContractError::UnknownVersion)
+        })?;
+        if chunk_index >= chunk_count {
+            msg!("Chunk index out of range");
+            return Err(ContractError::InvalidChunkIndex.into());
+        }
+    }
+    if epoch.is_proven(chunk_index) {
+        msg!("Chunk already proven by the submission");
+        return Err(ContractError::ChunkAlreadyProven.into());
+    }
+
+    let (global_pda, _) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+
+    let reward_mint = {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
+        global_state.reward_mint
+    };
+    if reward_mint != *reward_mint_account.key() {
+        msg!("Reward mint mismatch");
+        return Err(ContractError::RewardMintMismatch.into());
+    }
+
+    // Bonds are held in a single vault at a PDA so that resolution can only
+    // ever pay a bond out of the account it was paid into.
+    let (expected_vault, vault_bump) = bond_vault_pda(program_id);
+    if expected_vault != *bond_vault.key() {
+        msg!("Bond vault mismatch");
+        return Err(ContractError::BondVaultMismatch.into());
+    }
+    if bond_vault.data_is_empty() {
+        let bump_arr = [vault_bump];
+        let seeds = seeds!(BOND_VAULT_SEED, &bump_arr);
+        create_pda_account(challenger, bond_vault, TokenAccount::LEN, &pinocchio_token::ID, &seeds)?;
+        InitializeAccount3 {
+            account: bond_vault,
+            mint: reward_mint_account,
+            owner: &global_pda,
+        }.invoke()?;
+    }
+
+    let (expected_challenge, bump) = challenge_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_challenge != *challenge_account.key() {
+        msg!("Challenge PDA mismatch");
+        return Err(ContractError::ChallengePdaMismatch.into());
+    }
+
+    // The bond is escrowed until the challenge is resolved.
+    Transfer {
+        from: challenger_token_account,
+        to: bond_vault,
+        authority: challenger,
+        amount: config.challenge_bond,
+    }.invoke()?;
+
+    let eid_bytes = epoch_id.to_le_bytes();
+    let bump_arr = [bump];
+    let seeds = seeds!(CHALLENGE_SEED, epoch.object.as_ref(), epoch.miner.as_ref(), &eid_bytes, &bump_arr);
+    create_pda_account(challenger, challenge_account, ChallengeRecord::SPACE, program_id, &seeds)?;
+
+    let challenge = ChallengeRecord {
+        object_id: epoch.object_id.get(),
+        epoch_id,
+        challenger: *challenger.key(),
+        chunk_index,
+        bond: config.challenge_bond,
+        created_at: Clock::get()?.unix_timestamp,
+        status: ChallengeStatus::Pending,
+        bump,
+    };
+    let mut challenge_data = challenge_account.try_borrow_mut_data()?;
+    challenge.store(&mut challenge_data)?;
+
+    // The miner's reputation only suffers if the challenge is upheld, so
+    // filing one costs the challenger a bond and the miner nothing.
+    epoch.set_status(EpochStatus::Challenged);
+
+    ContractEvent::EpochChallenged {
+        object_id: epoch.object_id.get(),
+        epoch_id,
+        miner: epoch.miner,
+        challenger: *challenger.key(),
+        chunk_index,
+        bond: challenge.bond,
+        timestamp: challenge.created_at,
+    }.emit();
     Ok(())
-}
\ No newline at end of file
+}
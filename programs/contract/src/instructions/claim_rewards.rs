@@ -0,0 +1,90 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, msg, pubkey::Pubkey, seeds, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use crate::{events::ContractEvent, error::ContractError, constants::GLOBAL_SEED, helpers::{check_program_account, check_program_id, check_writable, global_pda, miner_pda, next_account}, state::{EmissionMode, GlobalState, MinerAccount, ZeroCopy}};
+use pinocchio_token::instructions::{MintTo, Transfer};
+
+pub fn claim_rewards(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let miner = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let reward_source = next_account(accounts_iter)?; // reward_vault, or reward_mint in Mint mode
+    let miner_token_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+
+    if !miner.is_signer() {
+        msg!("Miner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    let (expected_miner, _) = miner_pda(program_id, miner.key());
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+    check_program_account(program_id, miner_account, true)?;
+
+    let (global_pda, bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+
+    let emission_mode = global_state.emission_mode()?;
+    let expected_source = match emission_mode {
+        EmissionMode::Vault => global_state.reward_vault,
+        EmissionMode::Mint => global_state.reward_mint,
+    };
+    if expected_source != *reward_source.key() {
+        msg!("Reward source mismatch");
+        return Err(ContractError::RewardSourceMismatch.into());
+    }
+
+    let mut miner_data = miner_account.try_borrow_mut_data()?;
+    let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+
+    let amount = miner_acc.pending_rewards.get();
+    if amount == 0 {
+        msg!("No pending rewards");
+        return Err(ContractError::NoPendingRewards.into());
+    }
+
+    miner_acc.pending_rewards.set(0);
+
+    if emission_mode == EmissionMode::Mint {
+        check_writable(global_account)?;
+        global_state.total_minted.set(global_state.total_minted.get().saturating_add(amount));
+    }
+    // The global account is passed to the token program as the authority.
+    drop(global_data);
+
+    let seed_bump_arr = [bump];
+    let seeds = seeds!(GLOBAL_SEED, &seed_bump_arr);
+    let signer = Signer::from(&seeds);
+
+    match emission_mode {
+        EmissionMode::Vault => Transfer {
+            from: reward_source,
+            to: miner_token_account,
+            authority: global_account,
+            amount,
+        }.invoke_signed(&[signer])?,
+        EmissionMode::Mint => MintTo {
+            mint: reward_source,
+            account: miner_token_account,
+            mint_authority: global_account,
+            amount,
+        }.invoke_signed(&[signer])?,
+    }
+
+    ContractEvent::RewardsClaimed {
+        miner: *miner.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{challenge_pda, check_program_account, close_account, epoch_pda, next_account}, state::{ChallengeRecord, ChallengeStatus, EpochRecord, EpochStatus, ProgramAccount, ZeroCopy}};
+
+pub fn close_epoch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    epoch_id: u128,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let caller = next_account(accounts_iter)?;
+    let epoch_account = next_account(accounts_iter)?;
+    let recipient = next_account(accounts_iter)?;
+    let challenge_account = next_account(accounts_iter)?;
+
+    if !caller.is_signer() {
+        msg!("Caller must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    check_program_account(program_id, epoch_account, true)?;
+
+    let (object_id, object, miner) = {
+        let epoch_data = epoch_account.try_borrow_data()?;
+        let epoch = EpochRecord::load(&epoch_data)?;
+
+        if epoch.epoch_id.get() != epoch_id {
+            msg!("Epoch mismatch");
+            return Err(ContractError::EpochIdMismatch.into());
+        }
+
+        let (expected_epoch, _) = epoch_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+        if expected_epoch != *epoch_account.key() {
+            msg!("Epoch PDA mismatch");
+            return Err(ContractError::EpochPdaMismatch.into());
+        }
+
+        if !matches!(epoch.status()?, EpochStatus::Finalized | EpochStatus::Slashed | EpochStatus::Missed) {
+            msg!("Epoch not complete");
+            return Err(ContractError::EpochNotComplete.into());
+        }
+
+        // The rent goes back to whoever paid it when opening the epoch.
+        if *recipient.key() != epoch.opener {
+            msg!("Rent must be returned to the epoch opener");
+            return Err(ContractError::RentRecipientMismatch.into());
+        }
+
+        (epoch.object_id.get(), epoch.object, epoch.miner)
+    };
+
+    let (expected_challenge, _) = challenge_pda(program_id, &object, &miner, epoch_id);
+    if expected_challenge != *challenge_account.key() {
+        msg!("Challenge PDA mismatch");
+        return Err(ContractError::ChallengePdaMismatch.into());
+    }
+
+    // A challenge is settled with its epoch, so its record goes too, with the
+    // rent returned to the challenger who opened it.
+    if challenge_account.data_len() > 0 {
+        check_program_account(program_id, challenge_account, true)?;
+        let challenger = next_account(accounts_iter)?;
+        {
+            let challenge_data = challenge_account.try_borrow_data()?;
+            let challenge = ChallengeRecord::load(&challenge_data)?;
+            if matches!(challenge.status, ChallengeStatus::Pending | ChallengeStatus::Answered) {
+                msg!("Challenge not resolved");
+                return Err(ContractError::EpochUnderDispute.into());
+            }
+            if *challenger.key() != challenge.challenger {
+                msg!("Rent must be returned to the challenger");
+                return Err(ContractError::RentRecipientMismatch.into());
+            }
+        }
+        close_account(challenge_account, challenger)?;
+    }
+
+    close_account(epoch_account, recipient)?;
+
+    ContractEvent::EpochClosed {
+        object_id,
+        epoch_id,
+        miner,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, msg, pubkey::Pubkey, seeds, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::{instructions::CloseAccount, state::TokenAccount};
+
+use crate::{events::ContractEvent, constants::GLOBAL_SEED, error::ContractError, helpers::{check_program_account, check_program_id, check_token_account, close_account, escrow_pda, global_pda, global_transfer, next_account, object_pda}, state::{GlobalState, ObjectRecord, ZeroCopy}};
+
+pub fn close_object(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    object_id: u128,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let escrow_account = next_account(accounts_iter)?;
+    let owner_token_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+
+    if !owner.is_signer() {
+        msg!("Owner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    let (expected_escrow, _) = escrow_pda(program_id, object_account.key());
+    if expected_escrow != *escrow_account.key() {
+        msg!("Escrow PDA mismatch");
+        return Err(ContractError::EscrowPdaMismatch.into());
+    }
+
+    let (global_pda, global_bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
+        check_token_account(owner_token_account, owner.key(), &global_state.reward_mint)?;
+    }
+
+    check_program_account(program_id, object_account, true)?;
+    {
+        let object_data = object_account.try_borrow_data()?;
+        let object = ObjectRecord::load(&object_data)?;
+
+        let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+        if expected_object != *object_account.key() || object.object_id.get() != object_id {
+            msg!("Object PDA mismatch");
+            return Err(ContractError::ObjectPdaMismatch.into());
+        }
+
+        if object.owner != *owner.key() {
+            msg!("Signer is not the object owner");
+            return Err(ContractError::NotObjectOwner.into());
+        }
+
+        // Every replica has completed its retention, so no epoch can still
+        // draw on the escrow.
+        if !object.is_expired() {
+            msg!("Object retention has not ended");
+            return Err(ContractError::ObjectNotExpired.into());
+        }
+    }
+
+    // Whatever is left in escrow (refundable payments not yet claimed) goes
+    // back to the owner before the escrow and object accounts are closed.
+    let remaining = TokenAccount::from_account_info(escrow_account)?.amount();
+    global_transfer(escrow_account, owner_token_account, global_account, global_bump, remaining)?;
+
+    let bump_arr = [global_bump];
+    let seeds = seeds!(GLOBAL_SEED, &bump_arr);
+    CloseAccount {
+        account: escrow_account,
+        destination: owner,
+        authority: global_account,
+    }.invoke_signed(&[Signer::from(&seeds)])?;
+
+    close_account(object_account, owner)?;
+
+    ContractEvent::ObjectClosed {
+        object_id,
+        owner: *owner.key(),
+        refunded: remaining,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
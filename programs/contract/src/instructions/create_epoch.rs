@@ -1,58 +1,148 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, seeds, sysvars::{clock::Clock, Sysvar}, ProgramResult};
 
-use crate::{helpers::{epoch_pda, next_account}, state::{EpochRecord, EpochStatus, ObjectRecord}};
+use crate::{events::ContractEvent, constants::{CHALLENGE_DELAY_SLOTS, EPOCH_SEED}, error::ContractError, helpers::{check_program_account, check_program_id, create_pda_account, epoch_pda, global_pda, load_config, miner_pda, next_account, object_pda}, state::{EpochRecord, EpochStatus, GlobalState, MinerAccount, ObjectRecord, ZeroCopy}};
 
+/// Opens the next epoch of a miner's replica. Its id follows
+/// the replica's previous epoch rather than being chosen by the caller, and
+/// its challenges are only drawn when the proof is submitted, from the hash
+/// of a slot that does not exist yet.
 pub fn create_epoch(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     object_id: u128,
-    nonce: u64,
-    epoch_id: u128,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let caller = next_account(accounts_iter)?;
     let object_account = next_account(accounts_iter)?;
     let epoch_account = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
 
     if !caller.is_signer() {
         msg!("Caller must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    let config = load_config(program_id, config_account)?;
+    check_program_id(system_program, &pinocchio_system::ID)?;
+    check_program_account(program_id, object_account, true)?;
+    check_program_account(program_id, miner_account, true)?;
 
-    let object_data = object_account.try_borrow_data()?;
-    let _object: ObjectRecord = ObjectRecord::try_from_slice(&object_data).map_err(|_| {
-        msg!("Failed to deserialize object record");
-        ProgramError::InvalidAccountData
+    // Epochs are per (object, miner): each assigned miner opens its own and
+    // can be slashed via MarkMissed if no proof arrives by the deadline.
+    let miner = {
+        let mut miner_data = miner_account.try_borrow_mut_data()?;
+        let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+
+        let (expected_miner, _) = miner_pda(program_id, &miner_acc.miner);
+        if expected_miner != *miner_account.key() {
+            msg!("Miner account mismatch");
+            return Err(ContractError::MinerPdaMismatch.into());
+        }
+
+        // Counted here and released when the epoch completes, so the miner
+        // cannot deregister out from under an epoch it still owes.
+        miner_acc.open_epochs.set(miner_acc.open_epochs.get().saturating_add(1));
+        miner_acc.miner
+    };
+
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || object.object_id.get() != object_id {
+        msg!("Object account mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    let slot = object.assignment_slot(&miner).ok_or_else(|| {
+        msg!("Miner not assigned to this object");
+        ProgramError::from(ContractError::MinerNotAssigned)
     })?;
 
+    // Each opened epoch draws on the escrow prepaid for retention_epochs per replica.
+    let opened = object.epochs_opened[slot].get();
+    if opened >= object.retention_epochs.get() {
+        msg!("Retention exhausted");
+        return Err(ContractError::RetentionExhausted.into());
+    }
+
+    // A replica's epochs start at least epoch_duration_secs apart, so paid
+    // retention covers real time rather than however fast the miner can
+    // cycle. A miner that stops opening epochs would never be slashed, so
+    // once one is due anyone may open it. The opener pays the rent and gets
+    // it back from CloseEpoch.
     let clock = Clock::get()?;
-    let deadline = clock.unix_timestamp + 120;
+    if clock.unix_timestamp < object.next_epoch_ts[slot].get() {
+        msg!("Next epoch of this replica is not due yet");
+        return Err(ContractError::EpochNotDue.into());
+    }
+    object.epochs_opened[slot].set(opened + 1);
+    let epoch_id = u128::from(opened + 1);
+
+    let deadline = clock.unix_timestamp + config.epoch_duration_secs;
+    object.next_epoch_ts[slot].set(deadline);
 
-    let (expected_epoch, bump) = epoch_pda(program_id, object_id, epoch_id);
+    let (expected_epoch, bump) = epoch_pda(program_id, object_account.key(), &miner, epoch_id);
     if expected_epoch != *epoch_account.key() {
         msg!("Epoch PDA mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::EpochPdaMismatch.into());
     }
 
+    let eid_bytes = epoch_id.to_le_bytes();
+    let bump_arr = [bump];
+    let seeds = seeds!(EPOCH_SEED, object_account.key().as_ref(), miner.as_ref(), &eid_bytes, &bump_arr);
+    create_pda_account(caller, epoch_account, EpochRecord::SPACE, program_id, &seeds)?;
+
+    let (expected_global, _) = global_pda(program_id);
+    if expected_global != *global_account.key() {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, true)?;
+
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+    global_state.apply_decay(clock.unix_timestamp, config.decay_period_secs);
+    let reward = global_state.epoch_reward.get();
+
+    let seed_slot = clock.slot + CHALLENGE_DELAY_SLOTS;
+
     let epoch_record = EpochRecord {
-        object_id,
-        epoch_id,
-        nonce,
-        deadline_ts: deadline,
-        solver: None,
+        object_id: object_id.into(),
+        object: *object_account.key(),
+        epoch_id: epoch_id.into(),
+        nonce: 0.into(),
+        seed_slot: seed_slot.into(),
+        deadline_ts: deadline.into(),
+        challenges: Default::default(),
+        miner,
+        solver: None.into(),
         proof_hash: [0u8; 32],
-        status: EpochStatus::Open,
-        reward: 1_000_000,
+        submitted_at: 0.into(),
+        status: EpochStatus::Open as u8,
+        proven: 0,
+        reward: reward.into(),
+        payment: object.epoch_payment,
+        version: object.version,
+        opener: *caller.key(),
         bump,
     };
 
     let mut data = epoch_account.try_borrow_mut_data()?;
-    epoch_record.serialize(&mut &mut data[..]).map_err(|_| {
-        msg!("Failed to serialize epoch record");
-        ProgramError::InvalidAccountData
-    })?;
+    epoch_record.store(&mut data)?;
 
-    msg!(&format!("EVENT:EpochCreated:{}:{}", epoch_id, nonce));
+    ContractEvent::EpochCreated {
+        object_id,
+        epoch_id,
+        miner,
+        seed_slot,
+        deadline_ts: deadline,
+        reward,
+        payment: epoch_record.payment.get(),
+        version: epoch_record.version.get(),
+        timestamp: clock.unix_timestamp,
+    }.emit();
     Ok(())
 }
\ No newline at end of file
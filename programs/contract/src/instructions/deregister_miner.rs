@@ -0,0 +1,85 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::state::TokenAccount;
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, check_program_id, check_registry, check_stake_vault, close_account, global_close, global_pda, global_transfer, miner_pda, next_account, treasury_pda}, state::{MinerAccount, MinerRegistry, ZeroCopy}};
+
+pub fn deregister_miner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let miner = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let registry_account = next_account(accounts_iter)?;
+    let stake_vault = next_account(accounts_iter)?;
+    let treasury_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+
+    if !miner.is_signer() {
+        msg!("Miner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    let (expected_miner, _) = miner_pda(program_id, miner.key());
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+    check_program_account(program_id, miner_account, true)?;
+
+    {
+        let miner_data = miner_account.try_borrow_data()?;
+        let miner_acc = MinerAccount::load(&miner_data)?;
+
+        if miner_acc.stake.get() != 0 || miner_acc.pending_rewards.get() != 0 {
+            msg!("Miner still has stake or pending rewards");
+            return Err(ContractError::MinerNotEmpty.into());
+        }
+
+        // Epochs still owed would be left unable to finalise or be marked missed.
+        // Once they are settled the miner may leave, penalised or not.
+        if miner_acc.open_epochs.get() != 0 {
+            msg!("Miner still has open epochs");
+            return Err(ContractError::MinerHasOpenEpochs.into());
+        }
+
+        // A replica still assigned could never have its epochs opened.
+        if miner_acc.assignments.get() != 0 {
+            msg!("Miner still holds assigned replicas");
+            return Err(ContractError::MinerHasAssignments.into());
+        }
+    }
+
+    check_registry(program_id, registry_account)?;
+    {
+        let mut registry_data = registry_account.try_borrow_mut_data()?;
+        MinerRegistry::load_mut(&mut registry_data)?.remove(miner.key());
+    }
+
+    // The vault's rent goes back to the miner. With no stake recorded any
+    // tokens left in it were never the miner's, so they go to the treasury.
+    check_stake_vault(program_id, stake_vault, miner.key())?;
+    if !stake_vault.data_is_empty() {
+        check_program_id(token_program, &pinocchio_token::ID)?;
+        let (global_pda, global_bump) = global_pda(program_id);
+        if *global_account.key() != global_pda {
+            msg!("Global PDA mismatch");
+            return Err(ContractError::GlobalPdaMismatch.into());
+        }
+        let (expected_treasury, _) = treasury_pda(program_id);
+        if expected_treasury != *treasury_account.key() {
+            msg!("Treasury PDA mismatch");
+            return Err(ContractError::TreasuryPdaMismatch.into());
+        }
+
+        let residue = TokenAccount::from_account_info(stake_vault)?.amount();
+        global_transfer(stake_vault, treasury_account, global_account, global_bump, residue)?;
+        global_close(stake_vault, miner, global_account, global_bump)?;
+    }
+
+    close_account(miner_account, miner)?;
+
+    ContractEvent::MinerDeregistered {
+        miner: *miner.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::instructions::Transfer;
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, check_program_id, escrow_pda, miner_pda, next_account, object_pda}, state::{MinerAccount, ObjectRecord, ZeroCopy}};
+
+pub fn extend_retention(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    object_id: u128,
+    additional_epochs: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let owner_token_account = next_account(accounts_iter)?;
+    let escrow_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    // Remaining accounts are the miner accounts of replicas that have served
+    // the current retention, in slot order.
+
+    if !owner.is_signer() {
+        msg!("Owner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    if additional_epochs == 0 {
+        msg!("Nothing to extend");
+        return Err(ContractError::ZeroAmount.into());
+    }
+
+    let (expected_escrow, _) = escrow_pda(program_id, object_account.key());
+    if expected_escrow != *escrow_account.key() {
+        msg!("Escrow PDA mismatch");
+        return Err(ContractError::EscrowPdaMismatch.into());
+    }
+
+    check_program_account(program_id, object_account, true)?;
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || object.object_id.get() != object_id {
+        msg!("Object PDA mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    if object.owner != *owner.key() {
+        msg!("Signer is not the object owner");
+        return Err(ContractError::NotObjectOwner.into());
+    }
+
+    // The extra epochs are prepaid for every replica still held, at the
+    // current price.
+    let cost = object
+        .epoch_payment
+        .get()
+        .checked_mul(additional_epochs)
+        .and_then(|v| v.checked_mul(object.active_slots().count() as u64))
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // Replicas that had served the old retention owe the extra epochs, so
+    // their miners are assigned again.
+    let retention_epochs = object.retention_epochs.get();
+    let served: Vec<usize> = object.active_slots().filter(|&slot| object.epochs_completed[slot].get() >= retention_epochs).collect();
+    for slot in served {
+        let miner_account = next_account(accounts_iter)?;
+        let (expected_miner, _) = miner_pda(program_id, &object.assigned_miners[slot]);
+        if expected_miner != *miner_account.key() {
+            msg!("Miner account mismatch");
+            return Err(ContractError::MinerPdaMismatch.into());
+        }
+        check_program_account(program_id, miner_account, true)?;
+        let mut miner_data = miner_account.try_borrow_mut_data()?;
+        MinerAccount::load_mut(&mut miner_data)?.add_assignment();
+    }
+
+    object.retention_epochs.set(retention_epochs.checked_add(additional_epochs).ok_or(ProgramError::ArithmeticOverflow)?);
+    object.deposit.set(object.deposit.get().checked_add(cost).ok_or(ProgramError::ArithmeticOverflow)?);
+
+    Transfer {
+        from: owner_token_account,
+        to: escrow_account,
+        authority: owner,
+        amount: cost,
+    }.invoke()?;
+
+    ContractEvent::RetentionExtended {
+        object_id,
+        owner: *owner.key(),
+        retention_epochs: object.retention_epochs.get(),
+        cost,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
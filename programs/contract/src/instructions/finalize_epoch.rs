@@ -1,7 +1,5 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use pinocchio::{account_info::AccountInfo, instruction::Signer, msg, program_error::ProgramError, pubkey::Pubkey, seeds, ProgramResult};
-use crate::{constants::GLOBAL_SEED, helpers::{global_pda, next_account}, state::{EpochRecord, EpochStatus}};
-use pinocchio_token::instructions::{Transfer};
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, check_program_id, check_token_account, epoch_pda, escrow_pda, global_pda, global_transfer, load_config, miner_pda, next_account, object_pda}, state::{EpochRecord, EpochStatus, GlobalState, MinerAccount, ObjectRecord, ZeroCopy}};
 
 pub fn finalize_epoch(
     program_id: &Pubkey,
@@ -11,65 +9,117 @@ pub fn finalize_epoch(
     let accounts_iter = &mut accounts.iter();
     let caller = next_account(accounts_iter)?;
     let epoch_account = next_account(accounts_iter)?;
-    let _miner_account = next_account(accounts_iter)?;
-    let reward_vault = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let escrow_account = next_account(accounts_iter)?;
     let miner_token_account = next_account(accounts_iter)?;
     let token_program = next_account(accounts_iter)?;
-    let global_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
 
     if !caller.is_signer() {
         msg!("Caller must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    let config = load_config(program_id, config_account)?;
+    check_program_account(program_id, epoch_account, true)?;
+    check_program_account(program_id, miner_account, true)?;
+    check_program_account(program_id, global_account, true)?;
+    check_program_account(program_id, object_account, true)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
 
     let mut epoch_data = epoch_account.try_borrow_mut_data()?;
-    let mut epoch: EpochRecord = EpochRecord::try_from_slice(&epoch_data).map_err(|_| {
-        msg!("Failed to deserialize epoch data");
-        ProgramError::InvalidAccountData
-    })?;
+    let epoch = EpochRecord::load_mut(&mut epoch_data)?;
 
-    if epoch.epoch_id != epoch_id {
+    if epoch.epoch_id.get() != epoch_id {
         msg!("Epoch mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::EpochIdMismatch.into());
+    }
+
+    let (expected_epoch, _) = epoch_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_epoch != *epoch_account.key() {
+        msg!("Epoch PDA mismatch");
+        return Err(ContractError::EpochPdaMismatch.into());
     }
 
-    if epoch.status == EpochStatus::Challenged {
+    if epoch.status()? == EpochStatus::Challenged {
         msg!("Epoch under dispute");
-        return Err(ProgramError::Custom(2));
+        return Err(ContractError::EpochUnderDispute.into());
     }
 
-    if epoch.status != EpochStatus::Submitted {
+    if epoch.status()? != EpochStatus::Submitted {
         msg!("Epoch not submitted");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::EpochNotSubmitted.into());
+    }
+
+    // Challengers get the whole dispute window to contest the proof before
+    // the payment and reward can be released.
+    let clock = Clock::get()?;
+    if clock.unix_timestamp < epoch.submitted_at.get().saturating_add(config.dispute_window_secs) {
+        msg!("Dispute window still open");
+        return Err(ContractError::DisputeWindowOpen.into());
+    }
+
+    let solver = epoch.solver.get().ok_or(ContractError::EpochNotSubmitted)?;
+    let (expected_miner, _) = miner_pda(program_id, &solver);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account does not match epoch solver");
+        return Err(ContractError::MinerNotSolver.into());
     }
 
-    let (global_pda, bump) = global_pda(program_id);
+    let served = {
+        let mut object_data = object_account.try_borrow_mut_data()?;
+        let object = ObjectRecord::load_mut(&mut object_data)?;
+
+        let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+        if expected_object != *object_account.key() || *object_account.key() != epoch.object {
+            msg!("Object account mismatch");
+            return Err(ContractError::ObjectPdaMismatch.into());
+        }
+
+        object.complete_epoch(&epoch.miner, epoch.version.get())
+    };
+
+    let (expected_escrow, _) = escrow_pda(program_id, object_account.key());
+    if expected_escrow != *escrow_account.key() {
+        msg!("Escrow PDA mismatch");
+        return Err(ContractError::EscrowPdaMismatch.into());
+    }
+
+    let (global_pda, global_bump) = global_pda(program_id);
     if *global_account.key() != global_pda {
         msg!("Global PDA mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::GlobalPdaMismatch.into());
     }
 
-    let amount = epoch.reward;
-    let seed_bump = bump.clone();
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+
+    check_token_account(miner_token_account, &solver, &global_state.reward_mint)?;
+
+    // Credit at most what is left under the emission cap; the tokens are paid
+    // out (or minted) by ClaimRewards. The storage payment comes straight out
+    // of the object escrow.
+    let amount = global_state.allocate(epoch.reward.get());
 
-    let seed_bump_arr = [seed_bump];
-    let seeds = seeds!(GLOBAL_SEED, &seed_bump_arr);
-    let signer = Signer::from(&seeds);
+    let mut miner_data = miner_account.try_borrow_mut_data()?;
+    let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+    miner_acc.pending_rewards.set(miner_acc.pending_rewards.get().saturating_add(amount));
+    miner_acc.complete_epoch(served);
 
-    // Use pinocchio token transfer
-    Transfer {
-        from: reward_vault,
-        to: miner_token_account,
-        authority: global_account,
-        amount,
-    }.invoke_signed(&[signer])?;
+    epoch.set_status(EpochStatus::Finalized);
 
-    epoch.status = EpochStatus::Finalized;
-    epoch.serialize(&mut &mut epoch_data[..]).map_err(|_| {
-        msg!("Failed to serialize epoch data");
-        ProgramError::InvalidAccountData
-    })?;
+    // The global account is passed to the token program as the authority.
+    drop(global_data);
+    global_transfer(escrow_account, miner_token_account, global_account, global_bump, epoch.payment.get())?;
 
-    msg!(&format!("EVENT:EpochFinalized:{}:{}", epoch_id, amount));
+    ContractEvent::EpochFinalized {
+        object_id: epoch.object_id.get(),
+        epoch_id,
+        miner: solver,
+        reward: amount,
+        payment: epoch.payment.get(),
+        timestamp: clock.unix_timestamp,
+    }.emit();
     Ok(())
 }
@@ -1,8 +1,7 @@
-use borsh::BorshSerialize;
-use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
-use pinocchio_token::instructions::InitializeMint;
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, seeds, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::{instructions::{InitializeAccount3, InitializeMint}, state::TokenAccount};
 
-use crate::{helpers::{global_pda, next_account}, state::GlobalState};
+use crate::{events::ContractEvent, error::ContractError, constants::{CONFIG_SEED, MAX_ADMINS, TREASURY_SEED}, helpers::{check_program_id, config_pda, create_pda_account, global_pda, next_account, treasury_pda}, state::{Config, ConfigParams, EmissionMode, GlobalState, ProgramAccount, ZeroCopy}};
 
 pub fn initialize(
     program_id: &Pubkey,
@@ -10,6 +9,7 @@ pub fn initialize(
     decay_n: u64,
     decay_d: u64,
     emission_cap: u64,
+    emission_mode: u8,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let admin = next_account(accounts_iter)?;
@@ -18,32 +18,58 @@ pub fn initialize(
     let reward_vault_account = next_account(accounts_iter)?;
     let token_program = next_account(accounts_iter)?;
     let rent_sysvar_account = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+    let treasury_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
 
     if !admin.is_signer() {
         msg!("Admin must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    check_program_id(token_program, &pinocchio_token::ID)?;
+    check_program_id(system_program, &pinocchio_system::ID)?;
+
+    if decay_d == 0 || decay_n > decay_d {
+        msg!("Decay must be a fraction no greater than one");
+        return Err(ContractError::InvalidDecay.into());
+    }
+
+    let emission_mode = EmissionMode::try_from(emission_mode).map_err(|_| {
+        msg!("Unknown emission mode");
+        ProgramError::from(ContractError::InvalidEmissionMode)
+    })?;
 
     let (expected_global, bump) = global_pda(program_id);
     if expected_global != *global_account.key() {
         msg!("Global PDA mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::GlobalPdaMismatch.into());
     }
 
+    let params = ConfigParams::default();
     let clock = Clock::get()?;
+    let mut admins = [Pubkey::default(); MAX_ADMINS];
+    admins[0] = *admin.key();
     let global_state = GlobalState {
         admin: *admin.key(),
         reward_vault: *reward_vault_account.key(),
-        emission_cap,
+        emission_cap: emission_cap.into(),
         reward_mint: *reward_mint_account.key(),
-        total_minted: 0,
-        decay_numerator: decay_n,
-        decay_denom: decay_d,
-        last_decay_at: clock.unix_timestamp,
+        emission_mode: emission_mode as u8,
+        total_minted: 0.into(),
+        total_allocated: 0.into(),
+        decay_numerator: decay_n.into(),
+        decay_denom: decay_d.into(),
+        last_decay_at: clock.unix_timestamp.into(),
+        epoch_reward: params.base_epoch_reward.into(),
+        pending_admin: None.into(),
+        paused: 0.into(),
+        admins, // a 1-of-1 set until SetAdmins
+        admin_count: 1,
+        admin_threshold: 1,
     };
 
     let mut data = global_account.try_borrow_mut_data()?;
-    global_state.serialize(&mut &mut data[..]).map_err(|_| ProgramError::AccountDataTooSmall)?;
+    global_state.store(&mut data)?;
 
 
     let mint_authority = expected_global;
@@ -55,6 +81,42 @@ pub fn initialize(
         mint_authority: &mint_authority,
         decimals
     }.invoke()?;
-    msg!("EVENT:Initialized");
+
+    // Slashed stake and forfeited bonds are paid into a treasury token account
+    // held by the global PDA.
+    let (expected_treasury, treasury_bump) = treasury_pda(program_id);
+    if expected_treasury != *treasury_account.key() {
+        msg!("Treasury PDA mismatch");
+        return Err(ContractError::TreasuryPdaMismatch.into());
+    }
+    let bump_arr = [treasury_bump];
+    let seeds = seeds!(TREASURY_SEED, &bump_arr);
+    create_pda_account(admin, treasury_account, TokenAccount::LEN, &pinocchio_token::ID, &seeds)?;
+    InitializeAccount3 {
+        account: treasury_account,
+        mint: reward_mint_account,
+        owner: &expected_global,
+    }.invoke()?;
+
+    // Protocol parameters start from the defaults in constants.rs.
+    let (expected_config, config_bump) = config_pda(program_id);
+    if expected_config != *config_account.key() {
+        msg!("Config PDA mismatch");
+        return Err(ContractError::ConfigPdaMismatch.into());
+    }
+    let bump_arr = [config_bump];
+    let seeds = seeds!(CONFIG_SEED, &bump_arr);
+    create_pda_account(admin, config_account, Config::SPACE, program_id, &seeds)?;
+    let config = Config { params, bump: config_bump };
+    let mut config_data = config_account.try_borrow_mut_data()?;
+    config.store(&mut config_data)?;
+
+    ContractEvent::Initialized {
+        admin: *admin.key(),
+        reward_mint: *reward_mint_account.key(),
+        emission_mode: global_state.emission_mode,
+        emission_cap,
+        timestamp: clock.unix_timestamp,
+    }.emit();
     Ok(())
 }
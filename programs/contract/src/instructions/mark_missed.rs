@@ -0,0 +1,110 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, check_program_id, check_stake_vault, epoch_pda, global_pda, global_transfer, load_config, miner_pda, next_account, object_pda, treasury_pda}, state::{EpochRecord, EpochStatus, MinerAccount, ObjectRecord, ZeroCopy}};
+
+pub fn mark_missed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    epoch_id: u128,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let caller = next_account(accounts_iter)?;
+    let epoch_account = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let stake_vault = next_account(accounts_iter)?;
+    let treasury_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+
+    if !caller.is_signer() {
+        msg!("Caller must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    let config = load_config(program_id, config_account)?;
+    check_program_account(program_id, epoch_account, true)?;
+    check_program_account(program_id, miner_account, true)?;
+    check_program_account(program_id, global_account, false)?;
+    check_program_account(program_id, object_account, true)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    let mut epoch_data = epoch_account.try_borrow_mut_data()?;
+    let epoch = EpochRecord::load_mut(&mut epoch_data)?;
+
+    if epoch.epoch_id.get() != epoch_id {
+        msg!("Epoch mismatch");
+        return Err(ContractError::EpochIdMismatch.into());
+    }
+
+    let (expected_epoch, _) = epoch_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_epoch != *epoch_account.key() {
+        msg!("Epoch PDA mismatch");
+        return Err(ContractError::EpochPdaMismatch.into());
+    }
+
+    if epoch.status()? != EpochStatus::Open {
+        msg!("Epoch not open");
+        return Err(ContractError::EpochNotOpen.into());
+    }
+
+    let clock = Clock::get()?;
+    if clock.unix_timestamp <= epoch.deadline_ts.get() {
+        msg!("Deadline not reached");
+        return Err(ContractError::DeadlineNotReached.into());
+    }
+
+    let (expected_miner, _) = miner_pda(program_id, &epoch.miner);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account does not match assigned miner");
+        return Err(ContractError::MinerNotAssigned.into());
+    }
+
+    let (global_pda, global_bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+
+    let (expected_treasury, _) = treasury_pda(program_id);
+    if expected_treasury != *treasury_account.key() {
+        msg!("Treasury PDA mismatch");
+        return Err(ContractError::TreasuryPdaMismatch.into());
+    }
+
+    check_stake_vault(program_id, stake_vault, &epoch.miner)?;
+
+    let mut miner_data = miner_account.try_borrow_mut_data()?;
+    let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+
+    let slashed = (miner_acc.stake.get() as u128 * config.missed_slash_bps as u128 / 10_000) as u64;
+    miner_acc.stake.set(miner_acc.stake.get().saturating_sub(slashed));
+    miner_acc.reputation.set(miner_acc.reputation.get().saturating_sub(config.missed_reputation_penalty));
+
+    // The unearned payment stays in escrow and becomes refundable to the owner.
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || *object_account.key() != epoch.object {
+        msg!("Object account mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    object.refundable.set(object.refundable.get().saturating_add(epoch.payment.get()));
+    let served = object.complete_epoch(&epoch.miner, epoch.version.get());
+    miner_acc.complete_epoch(served);
+
+    epoch.set_status(EpochStatus::Missed);
+
+    global_transfer(stake_vault, treasury_account, global_account, global_bump, slashed)?;
+
+    ContractEvent::EpochMissed {
+        object_id: epoch.object_id.get(),
+        epoch_id,
+        miner: epoch.miner,
+        slashed,
+        timestamp: clock.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use borsh::BorshDeserialize;
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, seeds, sysvars::{clock::Clock, rent::Rent, Sysvar}, ProgramResult};
+use pinocchio_system::instructions::Transfer;
+use pinocchio_token::{instructions::InitializeAccount3, state::TokenAccount};
+
+use crate::{events::ContractEvent, constants::{EPOCH_SEED, ESCROW_SEED}, error::ContractError, helpers::{check_program_account, check_program_id, close_account, create_pda_account, epoch_pda, escrow_pda, global_pda, miner_pda, next_account, object_pda}, legacy::{epoch_pda_v0, EpochRecordV0, GlobalStateV0, MinerAccountV0, ObjectRecordV0}, state::{AccountKind, ACCOUNT_HEADER_LEN, EpochRecord, EpochStatus, GlobalState, MinerAccount, ObjectRecord, ZeroCopy}};
+
+/// Upgrades a program account written by the first deployment to the current
+/// layout of `kind`, growing it and topping up rent from the payer when the
+/// new layout is larger.
+pub fn migrate(program_id: &Pubkey, accounts: &[AccountInfo], kind: AccountKind) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account(accounts_iter)?;
+    let account = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+
+    if !payer.is_signer() {
+        msg!("Payer must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    check_program_account(program_id, account, true)?;
+    check_program_id(system_program, &pinocchio_system::ID)?;
+
+    match kind {
+        AccountKind::Global => {
+            let global_state: GlobalState = upgrade_borsh::<GlobalStateV0>(account, &GlobalState::DISCRIMINATOR, GlobalState::VERSION)?.into();
+            let (expected_global, _) = global_pda(program_id);
+            if expected_global != *account.key() {
+                msg!("Global PDA mismatch");
+                return Err(ContractError::GlobalPdaMismatch.into());
+            }
+            write(payer, account, GlobalState::SPACE, GlobalState::VERSION, |data| global_state.store(data))
+        }
+        AccountKind::Object { object_id } => {
+            // The record did not hold its id, so the caller names it and the
+            // seeds confirm it.
+            let object = upgrade_borsh::<ObjectRecordV0>(account, &ObjectRecord::DISCRIMINATOR, ObjectRecord::VERSION)?.upgrade(object_id);
+            let (expected_object, _) = object_pda(program_id, &object.creator, object_id);
+            if expected_object != *account.key() {
+                msg!("Object account mismatch");
+                return Err(ContractError::ObjectPdaMismatch.into());
+            }
+
+            // Objects had no escrow; the payer opens an empty one so the
+            // instructions that settle against it work as for new objects.
+            let escrow_account = next_account(accounts_iter)?;
+            let reward_mint_account = next_account(accounts_iter)?;
+            let global_account = next_account(accounts_iter)?;
+            let token_program = next_account(accounts_iter)?;
+            check_program_id(token_program, &pinocchio_token::ID)?;
+
+            let (global_pda, _) = global_pda(program_id);
+            if *global_account.key() != global_pda {
+                msg!("Global PDA mismatch");
+                return Err(ContractError::GlobalPdaMismatch.into());
+            }
+            check_program_account(program_id, global_account, false)?;
+            {
+                let global_data = global_account.try_borrow_data()?;
+                let global_state = GlobalState::load(&global_data)?;
+                if global_state.reward_mint != *reward_mint_account.key() {
+                    msg!("Reward mint mismatch");
+                    return Err(ContractError::RewardMintMismatch.into());
+                }
+            }
+
+            let (expected_escrow, escrow_bump) = escrow_pda(program_id, account.key());
+            if expected_escrow != *escrow_account.key() {
+                msg!("Escrow PDA mismatch");
+                return Err(ContractError::EscrowPdaMismatch.into());
+            }
+            let escrow_bump_arr = [escrow_bump];
+            let escrow_seeds = seeds!(ESCROW_SEED, account.key(), &escrow_bump_arr);
+            create_pda_account(payer, escrow_account, TokenAccount::LEN, &pinocchio_token::ID, &escrow_seeds)?;
+            InitializeAccount3 {
+                account: escrow_account,
+                mint: reward_mint_account,
+                owner: &global_pda,
+            }.invoke()?;
+
+            write(payer, account, ObjectRecord::SPACE, ObjectRecord::VERSION, |data| object.store(data))
+        }
+        AccountKind::Epoch => {
+            let legacy = upgrade_borsh::<EpochRecordV0>(account, &EpochRecord::DISCRIMINATOR, EpochRecord::VERSION)?;
+            let (object_id, epoch_id) = (legacy.object_id, legacy.epoch_id);
+            let (expected_legacy, _) = epoch_pda_v0(program_id, object_id, epoch_id);
+            if expected_legacy != *account.key() {
+                msg!("Epoch PDA mismatch");
+                return Err(ContractError::EpochPdaMismatch.into());
+            }
+
+            // Epochs were seeded by object id alone and open to any solver, so
+            // none can stay where it is. Only a submitted one still owes its
+            // reward; it moves to the account seeded by its object and solver.
+            // The rest are settled and closed, the rent going to the payer.
+            let solver = match (legacy.status, legacy.solver) {
+                (EpochStatus::Submitted, Some(solver)) => solver,
+                _ => {
+                    close_account(account, payer)?;
+                    ContractEvent::EpochClosed {
+                        object_id,
+                        epoch_id,
+                        miner: legacy.solver.unwrap_or_default(),
+                        timestamp: Clock::get()?.unix_timestamp,
+                    }.emit();
+                    return Ok(());
+                }
+            };
+
+            let object_account = next_account(accounts_iter)?;
+            let epoch_account = next_account(accounts_iter)?;
+            let miner_account = next_account(accounts_iter)?;
+
+            check_program_account(program_id, object_account, false)?;
+            {
+                let object_data = object_account.try_borrow_data()?;
+                let object = ObjectRecord::load(&object_data)?;
+                let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+                if expected_object != *object_account.key() || object.object_id.get() != object_id {
+                    msg!("Object account mismatch");
+                    return Err(ContractError::ObjectPdaMismatch.into());
+                }
+            }
+
+            let (expected_epoch, bump) = epoch_pda(program_id, object_account.key(), &solver, epoch_id);
+            if expected_epoch != *epoch_account.key() {
+                msg!("Epoch PDA mismatch");
+                return Err(ContractError::EpochPdaMismatch.into());
+            }
+
+            // FinalizeEpoch or a challenge settles it against the solver's
+            // open epochs, so it counts as one.
+            let (expected_miner, _) = miner_pda(program_id, &solver);
+            if expected_miner != *miner_account.key() {
+                msg!("Miner account mismatch");
+                return Err(ContractError::MinerPdaMismatch.into());
+            }
+            check_program_account(program_id, miner_account, true)?;
+            {
+                let mut miner_data = miner_account.try_borrow_mut_data()?;
+                let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+                miner_acc.open_epochs.set(miner_acc.open_epochs.get().saturating_add(1));
+            }
+
+            let epoch = legacy.upgrade(*object_account.key(), solver, *payer.key(), bump);
+            let eid_bytes = epoch_id.to_le_bytes();
+            let bump_arr = [bump];
+            let seeds = seeds!(EPOCH_SEED, object_account.key(), &solver, &eid_bytes, &bump_arr);
+            create_pda_account(payer, epoch_account, EpochRecord::SPACE, program_id, &seeds)?;
+            close_account(account, payer)?;
+            write(payer, epoch_account, EpochRecord::SPACE, EpochRecord::VERSION, |data| epoch.store(data))
+        }
+        AccountKind::Miner => {
+            let miner_acc: MinerAccount = upgrade_borsh::<MinerAccountV0>(account, &MinerAccount::DISCRIMINATOR, MinerAccount::VERSION)?.into();
+            let (expected_miner, _) = miner_pda(program_id, &miner_acc.miner);
+            if expected_miner != *account.key() {
+                msg!("Miner account mismatch");
+                return Err(ContractError::MinerPdaMismatch.into());
+            }
+            // Miners join the registry on their next stake, once it holds
+            // the minimum.
+            write(payer, account, MinerAccount::SPACE, MinerAccount::VERSION, |data| miner_acc.store(data))
+        }
+    }
+}
+
+/// Reads the header-less Borsh body of an account now stored zero-copy at
+/// `version`. Tagged accounts are either current or of a type Migrate does
+/// not know.
+fn upgrade_borsh<T: BorshDeserialize>(account: &AccountInfo, discriminator: &[u8; 8], version: u8) -> Result<T, ProgramError> {
+    let data = account.try_borrow_data()?;
+
+    if data.len() >= ACCOUNT_HEADER_LEN && data[..8] == *discriminator {
+        if data[ACCOUNT_HEADER_LEN - 1] == version {
+            msg!("Account already migrated");
+            return Err(ContractError::AlreadyMigrated.into());
+        }
+        msg!("No migration path for this account version");
+        return Err(ContractError::AccountVersionMismatch.into());
+    }
+
+    decode(&data)
+}
+
+fn decode<T: BorshDeserialize>(body: &[u8]) -> Result<T, ProgramError> {
+    T::deserialize(&mut &body[..]).map_err(|_| {
+        msg!("Failed to deserialize legacy account");
+        ProgramError::InvalidAccountData
+    })
+}
+
+/// Resizes `account` to `space` if it is smaller and writes the upgraded
+/// value with `store`.
+fn write(
+    payer: &AccountInfo,
+    account: &AccountInfo,
+    space: usize,
+    version: u8,
+    store: impl FnOnce(&mut [u8]) -> ProgramResult,
+) -> ProgramResult {
+    if account.data_len() < space {
+        let lamports = Rent::get()?.minimum_balance(space);
+        if lamports > account.lamports() {
+            Transfer {
+                from: payer,
+                to: account,
+                lamports: lamports - account.lamports(),
+            }.invoke()?;
+        }
+        account.resize(space)?;
+    }
+
+    let mut data = account.try_borrow_mut_data()?;
+    store(&mut data)?;
+
+    ContractEvent::AccountMigrated {
+        account: *account.key(),
+        version,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
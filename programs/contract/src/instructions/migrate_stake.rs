@@ -0,0 +1,84 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::state::TokenAccount;
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_admin_quorum, check_program_account, check_program_id, check_token_account, global_pda, global_transfer, init_stake_vault, miner_pda, next_account}, state::{GlobalState, MinerAccount, ZeroCopy}};
+
+/// Moves the stake a miner recorded before per-miner vaults existed out of
+/// the shared vault it was deposited into and into the miner's own vault.
+pub fn migrate_stake(program_id: &Pubkey, accounts: &[AccountInfo], miner: Pubkey) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let stake_vault = next_account(accounts_iter)?;
+    let legacy_vault = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+    let reward_mint_account = next_account(accounts_iter)?;
+
+    if !admin.is_signer() {
+        msg!("Admin must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    check_program_id(token_program, &pinocchio_token::ID)?;
+    check_program_id(system_program, &pinocchio_system::ID)?;
+
+    let (global_pda, global_bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
+
+        // The source is chosen by the caller, so the move needs the same
+        // quorum as a slash: the first account plus any further admin signers
+        // passed after the reward mint.
+        check_admin_quorum(global_state, core::iter::once(admin).chain(accounts_iter))?;
+
+        if global_state.reward_mint != *reward_mint_account.key() {
+            msg!("Reward mint mismatch");
+            return Err(ContractError::RewardMintMismatch.into());
+        }
+        check_token_account(legacy_vault, &global_pda, &global_state.reward_mint)?;
+    }
+
+    let (expected_miner, _) = miner_pda(program_id, &miner);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+    check_program_account(program_id, miner_account, false)?;
+
+    let stake = {
+        let miner_data = miner_account.try_borrow_data()?;
+        MinerAccount::load(&miner_data)?.stake.get()
+    };
+
+    init_stake_vault(program_id, admin, stake_vault, &miner, reward_mint_account, &global_pda)?;
+    if legacy_vault.key() == stake_vault.key() {
+        msg!("Source is the miner's own vault");
+        return Err(ContractError::StakeVaultMismatch.into());
+    }
+
+    // Only the part of the recorded stake the vault does not yet hold moves,
+    // so a miner is migrated once.
+    let held = TokenAccount::from_account_info(stake_vault)?.amount();
+    let amount = stake.saturating_sub(held);
+    if amount == 0 {
+        msg!("Stake already held in the miner's vault");
+        return Err(ContractError::AlreadyMigrated.into());
+    }
+
+    global_transfer(legacy_vault, stake_vault, global_account, global_bump, amount)?;
+
+    ContractEvent::StakeMigrated {
+        miner,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -3,17 +3,59 @@ pub mod register_object;
 pub mod create_epoch;
 pub mod submit_proof;
 pub mod challenge_proof;
+pub mod answer_challenge;
 pub mod stake_tokens;
 pub mod unstake_tokens;
 pub mod slash_miner;
 pub mod finalize_epoch;
+pub mod register_miner;
+pub mod deregister_miner;
+pub mod claim_rewards;
+pub mod resolve_challenge;
+pub mod mark_missed;
+pub mod refund_escrow;
+pub mod close_object;
+pub mod close_epoch;
+pub mod extend_retention;
+pub mod update_commitment;
+pub mod transfer_object;
+pub mod accept_object;
+pub mod release_replica;
+pub mod propose_admin;
+pub mod accept_admin;
+pub mod set_pause;
+pub mod set_admins;
+pub mod update_config;
+pub mod migrate;
+pub mod migrate_stake;
 
 pub use initialise::*;
 pub use register_object::*;
 pub use create_epoch::*;
 pub use submit_proof::*;
 pub use challenge_proof::*;
+pub use answer_challenge::*;
 pub use stake_tokens::*;
 pub use unstake_tokens::*;
 pub use slash_miner::*;
-pub use finalize_epoch::*;
\ No newline at end of file
+pub use finalize_epoch::*;
+pub use register_miner::*;
+pub use deregister_miner::*;
+pub use claim_rewards::*;
+pub use resolve_challenge::*;
+pub use mark_missed::*;
+pub use refund_escrow::*;
+pub use close_object::*;
+pub use close_epoch::*;
+pub use extend_retention::*;
+pub use update_commitment::*;
+pub use transfer_object::*;
+pub use accept_object::*;
+pub use release_replica::*;
+pub use propose_admin::*;
+pub use accept_admin::*;
+pub use set_pause::*;
+pub use set_admins::*;
+pub use update_config::*;
+pub use migrate::*;
+pub use migrate_stake::*;
\ No newline at end of file
@@ -0,0 +1,75 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, check_program_id, check_token_account, escrow_pda, global_pda, global_transfer, next_account, object_pda}, state::{GlobalState, ObjectRecord, ZeroCopy}};
+
+pub fn refund_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    object_id: u128,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let escrow_account = next_account(accounts_iter)?;
+    let owner_token_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+
+    if !owner.is_signer() {
+        msg!("Owner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    let (expected_escrow, _) = escrow_pda(program_id, object_account.key());
+    if expected_escrow != *escrow_account.key() {
+        msg!("Escrow PDA mismatch");
+        return Err(ContractError::EscrowPdaMismatch.into());
+    }
+
+    let (global_pda, global_bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
+        check_token_account(owner_token_account, owner.key(), &global_state.reward_mint)?;
+    }
+
+    check_program_account(program_id, object_account, true)?;
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || object.object_id.get() != object_id {
+        msg!("Object PDA mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    if object.owner != *owner.key() {
+        msg!("Signer is not the object owner");
+        return Err(ContractError::NotObjectOwner.into());
+    }
+
+    let amount = object.refundable.get();
+    if amount == 0 {
+        msg!("Nothing to refund");
+        return Err(ContractError::NothingToRefund.into());
+    }
+
+    object.refundable.set(0);
+
+    global_transfer(escrow_account, owner_token_account, global_account, global_bump, amount)?;
+
+    ContractEvent::EscrowRefunded {
+        object_id,
+        owner: *owner.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, seeds, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, constants::{INITIAL_REPUTATION, MINER_METADATA_LEN, MINER_SEED}, helpers::{check_program_id, create_pda_account, miner_pda, next_account}, state::{MinerAccount, ZeroCopy}};
+
+pub fn register_miner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    operator: Pubkey,
+    metadata: [u8; MINER_METADATA_LEN],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let miner = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+
+    if !miner.is_signer() {
+        msg!("Miner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    check_program_id(system_program, &pinocchio_system::ID)?;
+
+    let (expected_miner, bump) = miner_pda(program_id, miner.key());
+    if expected_miner != *miner_account.key() {
+        msg!("Miner PDA mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+
+    let bump_arr = [bump];
+    let seeds = seeds!(MINER_SEED, miner.key(), &bump_arr);
+    create_pda_account(miner, miner_account, MinerAccount::SPACE, program_id, &seeds)?;
+
+    let miner_acc = MinerAccount {
+        miner: *miner.key(),
+        operator,
+        stake: 0.into(),
+        pending_rewards: 0.into(),
+        reputation: INITIAL_REPUTATION.into(),
+        unstake_ts: 0.into(),
+        metadata,
+        open_epochs: 0.into(),
+        assignments: 0.into(),
+        bump,
+    };
+
+    let mut data = miner_account.try_borrow_mut_data()?;
+    miner_acc.store(&mut data)?;
+
+    ContractEvent::MinerRegistered {
+        miner: *miner.key(),
+        operator,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
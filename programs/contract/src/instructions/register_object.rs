@@ -1,7 +1,8 @@
-use borsh::BorshSerialize;
-use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, seeds, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::{instructions::{InitializeAccount3, Transfer}, state::TokenAccount};
+use solana_sha256_hasher::hashv;
 
-use crate::{helpers::{next_account, object_pda}, state::{ObjectRecord, ProofType}};
+use crate::{events::ContractEvent, constants::{ESCROW_SEED, MAX_CHUNK_COUNT, MAX_REPLICATION, OBJECT_SEED, VERSION_HISTORY}, error::ContractError, helpers::{check_program_account, check_program_id, check_registry, check_writable, create_pda_account, escrow_pda, global_pda, load_config, miner_pda, next_account, object_pda, recent_slot_hash, select_miners}, merkle, state::{GlobalState, MinerAccount, MinerRegistry, ObjectRecord, PreviousVersion, ProofType, ZeroCopy}};
 
 pub fn register_object(
     program_id: &Pubkey,
@@ -9,47 +10,219 @@ pub fn register_object(
     commitment: [u8; 32],
     proof_type: u8,
     size: u64,
+    chunk_count: u64,
     retention_epochs: u64,
     object_id: u128,
+    replication_factor: u8,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let owner = next_account(accounts_iter)?;
     let object_account = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+    let slot_hashes_sysvar = next_account(accounts_iter)?;
+    let owner_token_account = next_account(accounts_iter)?;
+    let escrow_account = next_account(accounts_iter)?;
+    let reward_mint_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+    let registry_account = next_account(accounts_iter)?;
+    // Remaining accounts are the accounts of every registered miner, in
+    // registry order.
 
     if !owner.is_signer() {
         msg!("Owner must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    let config = load_config(program_id, config_account)?;
+    check_program_id(system_program, &pinocchio_system::ID)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
 
     let (expected_object, bump) = object_pda(program_id, owner.key(), object_id);
     if expected_object != *object_account.key() {
         msg!("Object PDA mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::ObjectPdaMismatch.into());
     }
 
-    let proof_type_enum = match proof_type {
-        0 => ProofType::CompactHash,
-        1 => ProofType::Snark,
-        x => ProofType::Other(x),
-    };
+    // Only Merkle-committed content can be checked by SubmitProof; any other
+    // proof type would be accepted unverified.
+    if ProofType::from(proof_type) != ProofType::CompactHash {
+        msg!("Unsupported proof type");
+        return Err(ContractError::UnsupportedProofType.into());
+    }
+
+    if chunk_count != merkle::chunk_count(size) {
+        msg!("Chunk count does not match object size");
+        return Err(ContractError::InvalidChunkCount.into());
+    }
+
+    if chunk_count > MAX_CHUNK_COUNT {
+        msg!("Object exceeds the maximum chunk count");
+        return Err(ContractError::InvalidChunkCount.into());
+    }
+
+    if replication_factor == 0 || replication_factor as usize > MAX_REPLICATION {
+        msg!("Replication factor out of range");
+        return Err(ContractError::InvalidReplicationFactor.into());
+    }
+
+    check_registry(program_id, registry_account)?;
+    let mut registry_data = registry_account.try_borrow_mut_data()?;
+    let registry = MinerRegistry::load_mut(&mut registry_data)?;
+
+    // The owner passes the whole registry, so it cannot choose who is drawn.
+    // Miners slashed below the minimum stake, or left below a raised one,
+    // are not drawn and lose their registry slot.
+    if accounts_iter.len() != registry.miners().len() {
+        msg!("Candidates must be every registered miner");
+        return Err(ContractError::InvalidCandidate.into());
+    }
+
+    let candidate_accounts = accounts_iter.as_slice();
+    let mut candidates: Vec<(Pubkey, u64)> = Vec::new();
+    let mut evicted: Vec<Pubkey> = Vec::new();
+    for (candidate, registered) in accounts_iter.zip(registry.miners()) {
+        if !candidate.is_owned_by(program_id) {
+            msg!("Candidate is not a miner account");
+            return Err(ContractError::InvalidCandidate.into());
+        }
+
+        let candidate_data = candidate.try_borrow_data()?;
+        let miner_acc = MinerAccount::load(&candidate_data)?;
+
+        let (expected_miner, _) = miner_pda(program_id, &miner_acc.miner);
+        if expected_miner != *candidate.key() {
+            msg!("Miner account mismatch");
+            return Err(ContractError::MinerPdaMismatch.into());
+        }
+
+        if miner_acc.miner != *registered {
+            msg!("Candidate out of registry order");
+            return Err(ContractError::InvalidCandidate.into());
+        }
+
+        if miner_acc.stake.get() < config.min_assignment_stake {
+            evicted.push(miner_acc.miner);
+            continue;
+        }
+        candidates.push((miner_acc.miner, miner_acc.stake.get()));
+    }
+    for miner in &evicted {
+        registry.remove(miner);
+    }
+
+    if candidates.len() < replication_factor as usize {
+        msg!("Not enough eligible miners");
+        return Err(ContractError::NotEnoughMiners.into());
+    }
+
+    // Nothing the owner picks, such as the object id, feeds the seed.
+    let draws = registry.draws.get();
+    registry.draws.set(draws + 1);
+    let seed = hashv(&[&recent_slot_hash(slot_hashes_sysvar)?, &draws.to_le_bytes()]).to_bytes();
+    let assigned_miners = select_miners(&mut candidates, &seed, replication_factor as usize);
+
+    // Each chosen miner holds its replica until it has served the retention
+    // or is released, and cannot unstake below the minimum meanwhile.
+    if retention_epochs > 0 {
+        for candidate in candidate_accounts {
+            let mut candidate_data = candidate.try_borrow_mut_data()?;
+            let miner_acc = MinerAccount::load_mut(&mut candidate_data)?;
+            if assigned_miners.contains(&miner_acc.miner) {
+                check_writable(candidate)?;
+                miner_acc.add_assignment();
+            }
+        }
+    }
+
+    // The owner prepays every epoch of every replica into the object escrow.
+    let epoch_payment = chunk_count.checked_mul(config.price_per_chunk_epoch).ok_or(ProgramError::ArithmeticOverflow)?;
+    let deposit = epoch_payment
+        .checked_mul(retention_epochs)
+        .and_then(|v| v.checked_mul(replication_factor as u64))
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let (global_pda, _) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
+        if global_state.reward_mint != *reward_mint_account.key() {
+            msg!("Reward mint mismatch");
+            return Err(ContractError::RewardMintMismatch.into());
+        }
+    }
+
+    let (expected_escrow, escrow_bump) = escrow_pda(program_id, object_account.key());
+    if expected_escrow != *escrow_account.key() {
+        msg!("Escrow PDA mismatch");
+        return Err(ContractError::EscrowPdaMismatch.into());
+    }
+
+    let id_bytes = object_id.to_le_bytes();
+    let bump_arr = [bump];
+    let seeds = seeds!(OBJECT_SEED, owner.key(), &id_bytes, &bump_arr);
+    create_pda_account(owner, object_account, ObjectRecord::SPACE, program_id, &seeds)?;
+
+    let escrow_bump_arr = [escrow_bump];
+    let escrow_seeds = seeds!(ESCROW_SEED, object_account.key(), &escrow_bump_arr);
+    create_pda_account(owner, escrow_account, TokenAccount::LEN, &pinocchio_token::ID, &escrow_seeds)?;
+    InitializeAccount3 {
+        account: escrow_account,
+        mint: reward_mint_account,
+        owner: &global_pda,
+    }.invoke()?;
+
+    Transfer {
+        from: owner_token_account,
+        to: escrow_account,
+        authority: owner,
+        amount: deposit,
+    }.invoke()?;
 
     let clock = Clock::get()?;
     let object_record = ObjectRecord {
         owner: *owner.key(),
+        creator: *owner.key(),
+        object_id: object_id.into(),
         commitment,
-        proof_type: proof_type_enum,
-        size,
-        created_ts: clock.unix_timestamp,
-        retention_epochs,
+        proof_type,
+        size: size.into(),
+        chunk_count: chunk_count.into(),
+        created_ts: clock.unix_timestamp.into(),
+        retention_epochs: retention_epochs.into(),
+        replication_factor,
+        assigned_miners,
+        epoch_payment: epoch_payment.into(),
+        deposit: deposit.into(),
+        epochs_opened: [0.into(); MAX_REPLICATION],
+        epochs_completed: [0.into(); MAX_REPLICATION],
+        next_epoch_ts: [clock.unix_timestamp.into(); MAX_REPLICATION],
+        refundable: 0.into(),
+        version: 0.into(),
+        previous_versions: [PreviousVersion::default(); VERSION_HISTORY],
+        pending_owner: None.into(),
         bump,
     };
 
     let mut data = object_account.try_borrow_mut_data()?;
-    object_record.serialize(&mut &mut data[..]).map_err(|_| {
-        msg!("Failed to serialize object record");
-        ProgramError::InvalidAccountData
-    })?;
+    object_record.store(&mut data)?;
 
-    msg!(&format!("EVENT:ObjectRegistered:{}", object_id));
+    ContractEvent::ObjectRegistered {
+        object_id,
+        owner: *owner.key(),
+        commitment,
+        size,
+        chunk_count,
+        retention_epochs,
+        assigned_miners: object_record.assigned_miners[..replication_factor as usize].to_vec(),
+        deposit,
+        timestamp: clock.unix_timestamp,
+    }.emit();
     Ok(())
 }
\ No newline at end of file
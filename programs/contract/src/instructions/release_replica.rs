@@ -0,0 +1,89 @@
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, load_config, miner_pda, next_account, object_pda}, state::{MinerAccount, ObjectRecord, ZeroCopy}};
+
+/// Drops a replica whose miner has let it stall for a full epoch past the
+/// point its next epoch was due, returning the escrow for the epochs it will
+/// never serve to the owner.
+pub fn release_replica(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    object_id: u128,
+    miner: Pubkey,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+
+    if !owner.is_signer() {
+        msg!("Owner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    let config = load_config(program_id, config_account)?;
+
+    check_program_account(program_id, object_account, true)?;
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || object.object_id.get() != object_id {
+        msg!("Object PDA mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    if object.owner != *owner.key() {
+        msg!("Signer is not the object owner");
+        return Err(ContractError::NotObjectOwner.into());
+    }
+
+    let slot = object.assignment_slot(&miner).filter(|&slot| !object.is_released(slot)).ok_or_else(|| {
+        msg!("Miner not assigned to this object");
+        ProgramError::from(ContractError::MinerNotAssigned)
+    })?;
+
+    // An open epoch still settles through FinalizeEpoch or MarkMissed, which
+    // look the replica up by miner.
+    let opened = object.epochs_opened[slot].get();
+    if opened != object.epochs_completed[slot].get() {
+        msg!("Replica has an open epoch");
+        return Err(ContractError::EpochNotComplete.into());
+    }
+
+    let remaining = object.retention_epochs.get().saturating_sub(opened);
+    if remaining == 0 {
+        msg!("Replica has served its full retention");
+        return Err(ContractError::RetentionExhausted.into());
+    }
+
+    let clock = Clock::get()?;
+    let overdue_at = object.next_epoch_ts[slot].get().saturating_add(config.epoch_duration_secs);
+    if clock.unix_timestamp < overdue_at {
+        msg!("Replica is not overdue");
+        return Err(ContractError::ReplicaNotOverdue.into());
+    }
+
+    let refunded = object.epoch_payment.get().checked_mul(remaining).ok_or(ProgramError::ArithmeticOverflow)?;
+    object.deposit.set(object.deposit.get().saturating_sub(refunded));
+    object.refundable.set(object.refundable.get().checked_add(refunded).ok_or(ProgramError::ArithmeticOverflow)?);
+    object.assigned_miners[slot] = Pubkey::default();
+
+    // The miner no longer holds the replica, so its stake is free of it.
+    let (expected_miner, _) = miner_pda(program_id, &miner);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+    check_program_account(program_id, miner_account, true)?;
+    let mut miner_data = miner_account.try_borrow_mut_data()?;
+    MinerAccount::load_mut(&mut miner_data)?.end_assignment();
+
+    ContractEvent::ReplicaReleased {
+        object_id,
+        miner,
+        refunded,
+        timestamp: clock.unix_timestamp,
+    }.emit();
+    Ok(())
+}
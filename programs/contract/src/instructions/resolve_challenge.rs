@@ -0,0 +1,253 @@
+use pinocchio::{
+    ProgramResult,
+    account_info::AccountInfo,
+    msg,
+    pubkey::Pubkey,
+    sysvars::{Sysvar, clock::Clock},
+};
+
+use crate::{
+    error::ContractError,
+    events::ContractEvent,
+    helpers::{
+        challenge_pda, check_bond_vault, check_program_account, check_program_id,
+        check_stake_vault, check_token_account, epoch_pda, escrow_pda, global_pda, global_transfer,
+        load_config, miner_pda, next_account, object_pda, treasury_pda,
+    },
+    state::{
+        ChallengeRecord, ChallengeStatus, EpochRecord, EpochStatus, GlobalState, MinerAccount,
+        ObjectRecord, ProgramAccount, ZeroCopy,
+    },
+};
+
+pub fn resolve_challenge(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    epoch_id: u128,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let caller = next_account(accounts_iter)?;
+    let epoch_account = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let miner_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let challenge_account = next_account(accounts_iter)?;
+    let bond_vault = next_account(accounts_iter)?;
+    let stake_vault = next_account(accounts_iter)?;
+    let challenger_token_account = next_account(accounts_iter)?;
+    let treasury_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let escrow_account = next_account(accounts_iter)?;
+    let miner_token_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+
+    if !caller.is_signer() {
+        msg!("Caller must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    let config = load_config(program_id, config_account)?;
+    check_program_account(program_id, epoch_account, true)?;
+    check_program_account(program_id, object_account, true)?;
+    check_program_account(program_id, miner_account, true)?;
+    check_program_account(program_id, global_account, true)?;
+    check_program_account(program_id, challenge_account, true)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    let mut epoch_data = epoch_account.try_borrow_mut_data()?;
+    let epoch = EpochRecord::load_mut(&mut epoch_data)?;
+
+    if epoch.epoch_id.get() != epoch_id {
+        msg!("Epoch mismatch");
+        return Err(ContractError::EpochIdMismatch.into());
+    }
+
+    let (expected_epoch, _) = epoch_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_epoch != *epoch_account.key() {
+        msg!("Epoch PDA mismatch");
+        return Err(ContractError::EpochPdaMismatch.into());
+    }
+
+    if epoch.status()? != EpochStatus::Challenged {
+        msg!("Epoch not under dispute");
+        return Err(ContractError::EpochNotChallenged.into());
+    }
+
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || *object_account.key() != epoch.object {
+        msg!("Object account mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    let solver = epoch.solver.get().ok_or(ContractError::EpochNotSubmitted)?;
+    let (expected_miner, _) = miner_pda(program_id, &solver);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account does not match epoch solver");
+        return Err(ContractError::MinerNotSolver.into());
+    }
+
+    let (global_pda, bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+
+    let (expected_challenge, _) = challenge_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_challenge != *challenge_account.key() {
+        msg!("Challenge PDA mismatch");
+        return Err(ContractError::ChallengePdaMismatch.into());
+    }
+
+    let (expected_escrow, _) = escrow_pda(program_id, object_account.key());
+    if expected_escrow != *escrow_account.key() {
+        msg!("Escrow PDA mismatch");
+        return Err(ContractError::EscrowPdaMismatch.into());
+    }
+
+    let (expected_treasury, _) = treasury_pda(program_id);
+    if expected_treasury != *treasury_account.key() {
+        msg!("Treasury PDA mismatch");
+        return Err(ContractError::TreasuryPdaMismatch.into());
+    }
+
+    let mut challenge_data = challenge_account.try_borrow_mut_data()?;
+    let mut challenge = ChallengeRecord::load(&challenge_data)?;
+
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+
+    let mut miner_data = miner_account.try_borrow_mut_data()?;
+    let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+
+    // The solver answers with AnswerChallenge, producing the challenged chunk
+    // within the dispute window, and the challenge is rejected. A challenge
+    // left unanswered once the window has passed is upheld.
+    let clock = Clock::get()?;
+    let upheld = match challenge.status {
+        ChallengeStatus::Answered => false,
+        ChallengeStatus::Pending => {
+            if clock.unix_timestamp
+                < challenge
+                    .created_at
+                    .saturating_add(config.dispute_window_secs)
+            {
+                msg!("Dispute window still open");
+                return Err(ContractError::DisputeWindowOpen.into());
+            }
+            true
+        }
+        ChallengeStatus::Upheld | ChallengeStatus::Rejected => {
+            msg!("Challenge already resolved");
+            return Err(ContractError::ChallengeResolved.into());
+        }
+    };
+
+    check_bond_vault(program_id, bond_vault)?;
+
+    // Slashed stake leaves the stake vault: the challenger's share goes to them
+    // and the rest to the treasury, and the epoch payment becomes refundable to
+    // the owner. A failed challenge forfeits the bond to the treasury and the
+    // solver is paid from escrow.
+    let mut slashed = 0;
+    let mut challenger_share = 0;
+    let mut reward = 0;
+    if upheld {
+        check_stake_vault(program_id, stake_vault, &solver)?;
+        check_token_account(
+            challenger_token_account,
+            &challenge.challenger,
+            &global_state.reward_mint,
+        )?;
+
+        slashed = (miner_acc.stake.get() as u128 * config.slash_bps as u128 / 10_000) as u64;
+        challenger_share = (slashed as u128 * config.challenger_share_bps as u128 / 10_000) as u64;
+        miner_acc
+            .stake
+            .set(miner_acc.stake.get().saturating_sub(slashed));
+        let penalty = config
+            .challenge_reputation_penalty
+            .saturating_add(config.slash_reputation_penalty);
+        miner_acc
+            .reputation
+            .set(miner_acc.reputation.get().saturating_sub(penalty));
+        object
+            .refundable
+            .set(object.refundable.get().saturating_add(epoch.payment.get()));
+        epoch.set_status(EpochStatus::Slashed);
+        challenge.status = ChallengeStatus::Upheld;
+    } else {
+        check_token_account(miner_token_account, &solver, &global_state.reward_mint)?;
+
+        let amount = global_state.allocate(epoch.reward.get());
+
+        miner_acc
+            .pending_rewards
+            .set(miner_acc.pending_rewards.get().saturating_add(amount));
+        epoch.set_status(EpochStatus::Finalized);
+        challenge.status = ChallengeStatus::Rejected;
+        reward = amount;
+    }
+    let served = object.complete_epoch(&epoch.miner, epoch.version.get());
+    miner_acc.complete_epoch(served);
+
+    ContractEvent::ChallengeResolved {
+        object_id: epoch.object_id.get(),
+        epoch_id,
+        miner: epoch.miner,
+        challenger: challenge.challenger,
+        upheld,
+        slashed,
+        challenger_share,
+        reward,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+
+    challenge.store(&mut challenge_data)?;
+
+    // The global account is passed to the token program as the authority.
+    drop(global_data);
+
+    if upheld {
+        global_transfer(
+            bond_vault,
+            challenger_token_account,
+            global_account,
+            bump,
+            challenge.bond,
+        )?;
+        global_transfer(
+            stake_vault,
+            challenger_token_account,
+            global_account,
+            bump,
+            challenger_share,
+        )?;
+        global_transfer(
+            stake_vault,
+            treasury_account,
+            global_account,
+            bump,
+            slashed - challenger_share,
+        )?;
+    } else {
+        global_transfer(
+            bond_vault,
+            treasury_account,
+            global_account,
+            bump,
+            challenge.bond,
+        )?;
+        global_transfer(
+            escrow_account,
+            miner_token_account,
+            global_account,
+            bump,
+            epoch.payment.get(),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{constants::MAX_ADMINS, events::ContractEvent, error::ContractError, helpers::{check_admin_quorum, check_program_account, global_pda, next_account}, state::{GlobalState, ZeroCopy}};
+
+/// Replaces the M-of-N admin set. Approved by the current set: the first
+/// account and any further admin signers after the global account.
+pub fn set_admins(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    admins: Vec<Pubkey>,
+    threshold: u8,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let approver = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+
+    if !approver.is_signer() {
+        msg!("Admin must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    let (expected_global, _) = global_pda(program_id);
+    if expected_global != *global_account.key() {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, true)?;
+
+    if admins.is_empty() || admins.len() > MAX_ADMINS || threshold == 0 || threshold as usize > admins.len() {
+        msg!("Admin set must have 1..=MAX_ADMINS keys and a threshold within it");
+        return Err(ContractError::InvalidAdminSet.into());
+    }
+    for (i, admin) in admins.iter().enumerate() {
+        if admins[..i].contains(admin) {
+            msg!("Duplicate admin");
+            return Err(ContractError::InvalidAdminSet.into());
+        }
+    }
+
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+
+    check_admin_quorum(global_state, core::iter::once(approver).chain(accounts_iter))?;
+
+    global_state.admins = [Pubkey::default(); MAX_ADMINS];
+    global_state.admins[..admins.len()].copy_from_slice(&admins);
+    global_state.admin_count = admins.len() as u8;
+    global_state.admin_threshold = threshold;
+
+    ContractEvent::AdminSetUpdated {
+        admins,
+        threshold,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
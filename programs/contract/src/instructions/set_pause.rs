@@ -0,0 +1,43 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, global_pda, next_account}, state::{GlobalState, ZeroCopy}};
+
+/// Replaces the pause mask. Bit `RewardInstruction::pause_bit()` halts that
+/// instruction; `u64::MAX` halts everything but governance.
+pub fn set_pause(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+
+    if !admin.is_signer() {
+        msg!("Admin must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    let (expected_global, _) = global_pda(program_id);
+    if expected_global != *global_account.key() {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, true)?;
+
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+
+    if global_state.admin != *admin.key() {
+        msg!("Not authorized admin");
+        return Err(ContractError::NotAdmin.into());
+    }
+
+    global_state.paused.set(paused);
+
+    ContractEvent::PauseUpdated {
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -1,47 +1,72 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
-use crate::{helpers::next_account, state::{GlobalState, MinerAccount}};
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_admin_quorum, check_program_account, check_program_id, check_stake_vault, global_pda, global_transfer, load_config, miner_pda, next_account, treasury_pda}, state::{GlobalState, MinerAccount, ZeroCopy}};
 
 pub fn slash_miner(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _miner_pub: Pubkey,
+    miner_pub: Pubkey,
     amount: u64,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let admin = next_account(accounts_iter)?;
     let miner_account = next_account(accounts_iter)?;
     let global_account = next_account(accounts_iter)?;
+    let stake_vault = next_account(accounts_iter)?;
+    let treasury_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
 
     if !admin.is_signer() {
         msg!("Admin must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    let config = load_config(program_id, config_account)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
 
-    let global_data = global_account.try_borrow_data()?;
-    let global_state = GlobalState::try_from_slice(&global_data).map_err(|_| {
-        msg!("Failed to deserialize global state");
-        ProgramError::InvalidAccountData
-    })?;
+    let (global_pda, global_bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
 
-    if global_state.admin != *admin.key() {
-        msg!("Not authorized admin");
-        return Err(ProgramError::IllegalOwner);
+        // Slashing needs admin_threshold approvals: the first account plus any
+        // further admin signers passed after the config account.
+        check_admin_quorum(global_state, core::iter::once(admin).chain(accounts_iter))?;
     }
 
+    let (expected_miner, _) = miner_pda(program_id, &miner_pub);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+    check_program_account(program_id, miner_account, true)?;
+
+    let (expected_treasury, _) = treasury_pda(program_id);
+    if expected_treasury != *treasury_account.key() {
+        msg!("Treasury PDA mismatch");
+        return Err(ContractError::TreasuryPdaMismatch.into());
+    }
+    check_stake_vault(program_id, stake_vault, &miner_pub)?;
+
     let mut miner_data = miner_account.try_borrow_mut_data()?;
-    let mut miner_acc = MinerAccount::try_from_slice(&miner_data).map_err(|_| {
-        msg!("Failed to deserialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
-
-    miner_acc.stake = miner_acc.stake.saturating_sub(amount);
-    miner_acc.reputation = miner_acc.reputation.saturating_sub(10);
-    miner_acc.serialize(&mut &mut miner_data[..]).map_err(|_| {
-        msg!("Failed to serialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
-
-    msg!(&format!("EVENT:MinerSlashed:{}", amount));
+    let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+
+    let slashed = amount.min(miner_acc.stake.get());
+    miner_acc.stake.set(miner_acc.stake.get() - slashed);
+    miner_acc.reputation.set(miner_acc.reputation.get().saturating_sub(config.slash_reputation_penalty));
+
+    global_transfer(stake_vault, treasury_account, global_account, global_bump, slashed)?;
+
+    ContractEvent::MinerSlashed {
+        miner: miner_pub,
+        amount: slashed,
+        stake: miner_acc.stake.get(),
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
     Ok(())
-}
\ No newline at end of file
+}
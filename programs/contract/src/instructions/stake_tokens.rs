@@ -1,7 +1,6 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
-use crate::{helpers::next_account, state::MinerAccount};
-use pinocchio_token::instructions::{Transfer};
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_program_account, check_program_id, global_pda, init_stake_vault, join_registry, load_config, miner_pda, next_account}, state::{GlobalState, MinerAccount, ZeroCopy}};
+use pinocchio_token::instructions::Transfer;
 
 pub fn stake_tokens(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
@@ -9,12 +8,54 @@ pub fn stake_tokens(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64)
     let miner_token_account = next_account(accounts_iter)?;
     let stake_vault = next_account(accounts_iter)?;
     let miner_account = next_account(accounts_iter)?;
-    let _token_program = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let system_program = next_account(accounts_iter)?;
+    let reward_mint_account = next_account(accounts_iter)?;
+    let registry_account = next_account(accounts_iter)?;
 
     if !miner.is_signer() {
         msg!("Miner must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    let config = load_config(program_id, config_account)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
+    check_program_id(system_program, &pinocchio_system::ID)?;
+
+    let (expected_miner, _) = miner_pda(program_id, miner.key());
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+
+    if !miner_account.is_owned_by(program_id) || miner_account.data_is_empty() {
+        msg!("Miner not registered");
+        return Err(ContractError::MinerNotRegistered.into());
+    }
+    check_program_account(program_id, miner_account, true)?;
+
+    let (global_pda, _) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    let reward_mint = {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
+        global_state.reward_mint
+    };
+    if reward_mint != *reward_mint_account.key() {
+        msg!("Reward mint mismatch");
+        return Err(ContractError::RewardMintMismatch.into());
+    }
+
+    // Each miner's stake is held in its own vault at a PDA, owned by the
+    // global PDA. The vault is created on the first stake, which also covers
+    // miners registered before vaults existed.
+    init_stake_vault(program_id, miner, stake_vault, miner.key(), reward_mint_account, &global_pda)?;
 
     Transfer {
         from: miner_token_account,
@@ -24,19 +65,23 @@ pub fn stake_tokens(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64)
     }.invoke()?;
 
     let mut miner_data = miner_account.try_borrow_mut_data()?;
-    let mut miner_acc = MinerAccount::try_from_slice(&miner_data).map_err(|_| {
-        msg!("Failed to deserialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
-    miner_acc.stake = miner_acc.stake.saturating_add(amount);
+    let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
+    miner_acc.stake.set(miner_acc.stake.get().saturating_add(amount));
     
     let clock = Clock::get()?;
-    miner_acc.unstake_ts = clock.unix_timestamp + 3600;
-    miner_acc.serialize(&mut &mut miner_data[..]).map_err(|_| {
-        msg!("Failed to serialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
+    miner_acc.unstake_ts.set(clock.unix_timestamp + config.unstake_cooldown_secs);
 
-    msg!(&format!("EVENT:Staked:{:?}:{}", miner.key(), amount));
+    // Only miners that can be assigned hold a registry slot, so filling the
+    // registry costs the minimum stake per slot.
+    if miner_acc.stake.get() >= config.min_assignment_stake {
+        join_registry(program_id, miner, registry_account, miner.key())?;
+    }
+
+    ContractEvent::Staked {
+        miner: *miner.key(),
+        amount,
+        stake: miner_acc.stake.get(),
+        timestamp: clock.unix_timestamp,
+    }.emit();
     Ok(())
 }
\ No newline at end of file
@@ -1,97 +1,144 @@
-use borsh::{BorshDeserialize, BorshSerialize};
 use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
 
-use crate::{helpers::{miner_pda, next_account}, state::{EpochRecord, EpochStatus, GlobalState, MinerAccount}};
+use crate::{events::ContractEvent, constants::CHALLENGES_PER_EPOCH, error::ContractError, helpers::{check_program_account, epoch_nonce, epoch_pda, miner_pda, next_account, object_pda, seed_slot_hash}, merkle, state::{ChunkProof, EpochRecord, EpochStatus, MinerAccount, ObjectRecord, ZeroCopy, ProofType}};
 
 pub fn submit_proof(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     epoch_id: u128,
     proof_hash: [u8; 32],
+    proofs: Vec<ChunkProof>,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let miner = next_account(accounts_iter)?;
     let epoch_account = next_account(accounts_iter)?;
     let miner_account = next_account(accounts_iter)?;
-    let global_account = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let slot_hashes_sysvar = next_account(accounts_iter)?;
 
     if !miner.is_signer() {
         msg!("Miner must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
     }
+    check_program_account(program_id, epoch_account, true)?;
+    check_program_account(program_id, miner_account, false)?;
+    check_program_account(program_id, object_account, false)?;
 
     let mut epoch_data = epoch_account.try_borrow_mut_data()?;
-    let mut epoch: EpochRecord = EpochRecord::try_from_slice(&epoch_data).map_err(|_| {
-        msg!("Failed to deserialize epoch record");
-        ProgramError::InvalidAccountData
-    })?;
+    let epoch = EpochRecord::load_mut(&mut epoch_data)?;
 
-    if epoch.epoch_id != epoch_id {
+    if epoch.epoch_id.get() != epoch_id {
         msg!("Epoch ID mismatch");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::EpochIdMismatch.into());
     }
 
-    if epoch.status != EpochStatus::Open {
+    let (expected_epoch, _) = epoch_pda(program_id, &epoch.object, &epoch.miner, epoch_id);
+    if expected_epoch != *epoch_account.key() {
+        msg!("Epoch PDA mismatch");
+        return Err(ContractError::EpochPdaMismatch.into());
+    }
+
+    if epoch.status()? != EpochStatus::Open {
         msg!("Epoch not open");
-        return Err(ProgramError::InvalidArgument);
+        return Err(ContractError::EpochNotOpen.into());
     }
 
     let clock = Clock::get()?;
-    if clock.unix_timestamp > epoch.deadline_ts {
-        epoch.status = EpochStatus::Challenged;
-        epoch.serialize(&mut &mut epoch_data[..]).map_err(|_| {
-            msg!("Failed to serialize epoch record");
-            ProgramError::InvalidAccountData
-        })?;
+    if clock.unix_timestamp > epoch.deadline_ts.get() {
         msg!("Submission too late");
-        return Err(ProgramError::Custom(1));
+        return Err(ContractError::LateSubmission.into());
     }
 
-    epoch.solver = Some(*miner.key());
-    epoch.proof_hash = proof_hash;
-    epoch.status = EpochStatus::Submitted;
-    epoch.serialize(&mut &mut epoch_data[..]).map_err(|_| {
-        msg!("Failed to serialize epoch record");
-        ProgramError::InvalidAccountData
+    let object_data = object_account.try_borrow_data()?;
+    let object = ObjectRecord::load(&object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || *object_account.key() != epoch.object {
+        msg!("Object account mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    // RegisterObject only accepts CompactHash. Objects of other types that
+    // predate that check have no on-chain verifier; their proofs are recorded
+    // as submitted and left to the challenge process.
+    let (commitment, size, chunk_count) = object.content(epoch.version.get()).ok_or_else(|| {
+        msg!("Epoch version no longer retained");
+        ProgramError::from(ContractError::UnknownVersion)
     })?;
 
-    let (expected_miner, _) = miner_pda(program_id, miner.key());
-    if expected_miner != *miner_account.key() {
-        msg!("Miner account mismatch");
-        return Err(ProgramError::InvalidArgument);
+    // The seed slot lies past the one the epoch opened in, so the chunks to
+    // prove were unknown until then. They are drawn by the first submission.
+    if epoch.proven == 0 {
+        let seed = seed_slot_hash(slot_hashes_sysvar, epoch.seed_slot.get())?;
+        let nonce = epoch_nonce(&seed, &epoch.object, &epoch.miner, epoch_id);
+        epoch.nonce.set(nonce);
+        epoch.challenges = merkle::challenge_indices(nonce, epoch_id, chunk_count).map(Into::into);
+        epoch.proof_hash = merkle::response_seed(nonce);
+    }
+
+    // Challenged chunks may be proven over several submissions, in challenge
+    // order, so a path only has to fit in a transaction on its own. The proof
+    // is complete once the last one lands and the digest matches.
+    let mut complete = true;
+    if let ProofType::CompactHash = object.proof_type() {
+        let challenges = epoch.challenges();
+        let pending = &challenges[epoch.proven as usize..];
+        if proofs.is_empty()
+            || proofs.len() > pending.len()
+            || !merkle::verify_chunks(&commitment, size, chunk_count, &pending[..proofs.len()], &proofs)
+        {
+            msg!("Invalid storage proof");
+            return Err(ContractError::InvalidProof.into());
+        }
+        epoch.proof_hash = merkle::fold_response(epoch.proof_hash, &proofs);
+        epoch.proven += proofs.len() as u8;
+
+        complete = epoch.proven as usize == CHALLENGES_PER_EPOCH;
+        if complete && epoch.proof_hash != proof_hash {
+            msg!("Invalid storage proof");
+            return Err(ContractError::InvalidProof.into());
+        }
     }
 
-    let mut miner_data = miner_account.try_borrow_mut_data().map_err(|_| {
+    let miner_data = miner_account.try_borrow_data().map_err(|_| {
         msg!("Failed to borrow miner account data");
         ProgramError::InvalidAccountData
     })?;
-    let mut miner_acc = MinerAccount::try_from_slice(&miner_data).map_err(|_| {
-        msg!("Failed to deserialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
-    miner_acc.pending_rewards = miner_acc.pending_rewards.saturating_add(epoch.reward);
-    miner_acc.serialize(&mut &mut miner_data[..]).map_err(|_| {
-        msg!("Failed to serialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
+    let miner_acc = MinerAccount::load(&miner_data)?;
 
-    let mut global_data = global_account.try_borrow_mut_data().map_err(|_| {
-        msg!("Failed to borrow global account data");
-        ProgramError::InvalidAccountData
-    })?;
-    let mut global_state = GlobalState::try_from_slice(&global_data).map_err(|_| {
-        msg!("Failed to deserialize global state");
-        ProgramError::InvalidAccountData
-    })?;
-    if global_state.emission_cap >= epoch.reward {
-        global_state.total_minted = global_state.total_minted.saturating_add(epoch.reward);
-        global_state.emission_cap = global_state.emission_cap.saturating_sub(epoch.reward);
+    let (expected_miner, _) = miner_pda(program_id, &miner_acc.miner);
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
     }
-    global_state.serialize(&mut &mut global_data[..]).map_err(|_| {
-        msg!("Failed to serialize global state");
-        ProgramError::InvalidAccountData
-    })?;
 
-    msg!(&format!("EVENT:EpochSubmitted:{}", epoch_id));
+    if miner_acc.miner != epoch.miner {
+        msg!("Miner not assigned to this epoch");
+        return Err(ContractError::MinerNotAssigned.into());
+    }
+
+    if *miner.key() != miner_acc.miner && *miner.key() != miner_acc.operator {
+        msg!("Signer is neither the miner nor its operator");
+        return Err(ContractError::NotMinerOrOperator.into());
+    }
+
+    if !complete {
+        return Ok(());
+    }
+
+    // Rewards are credited once the epoch is finalised.
+    epoch.solver.set(Some(miner_acc.miner));
+    epoch.proof_hash = proof_hash;
+    epoch.submitted_at.set(clock.unix_timestamp);
+    epoch.set_status(EpochStatus::Submitted);
+
+    ContractEvent::ProofSubmitted {
+        object_id: epoch.object_id.get(),
+        epoch_id,
+        miner: miner_acc.miner,
+        nonce: epoch.nonce.get(),
+        proof_hash,
+        timestamp: clock.unix_timestamp,
+    }.emit();
     Ok(())
 }
\ No newline at end of file
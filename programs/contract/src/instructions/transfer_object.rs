@@ -0,0 +1,73 @@
+use pinocchio::{
+    ProgramResult,
+    account_info::AccountInfo,
+    msg,
+    pubkey::Pubkey,
+    sysvars::{Sysvar, clock::Clock},
+};
+
+use crate::{
+    error::ContractError,
+    events::ContractEvent,
+    helpers::{check_program_account, next_account, object_pda},
+    state::{ObjectRecord, ZeroCopy},
+};
+
+pub fn transfer_object(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    object_id: u128,
+    new_owner: Pubkey,
+    require_accept: bool,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+
+    if !owner.is_signer() {
+        msg!("Owner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    check_program_account(program_id, object_account, true)?;
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || object.object_id.get() != object_id {
+        msg!("Object PDA mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    if object.owner != *owner.key() {
+        msg!("Signer is not the object owner");
+        return Err(ContractError::NotObjectOwner.into());
+    }
+
+    // A two-step transfer only nominates the new owner, who must accept it;
+    // a direct one (or one back to the current owner) replaces any pending
+    // nomination. Escrow refunds and closing follow `owner`.
+    if require_accept && new_owner != object.owner {
+        object.pending_owner.set(Some(new_owner));
+        ContractEvent::ObjectTransferProposed {
+            object_id,
+            owner: object.owner,
+            pending_owner: new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        }
+        .emit();
+    } else {
+        let previous = object.owner;
+        object.owner = new_owner;
+        object.pending_owner.set(None);
+        ContractEvent::ObjectTransferred {
+            object_id,
+            from: previous,
+            to: new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        }
+        .emit();
+    }
+
+    Ok(())
+}
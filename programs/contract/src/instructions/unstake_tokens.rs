@@ -1,6 +1,5 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
-use crate::{constants::GLOBAL_SEED, helpers::{global_pda, next_account}, state::MinerAccount};
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use crate::{events::ContractEvent, error::ContractError, constants::GLOBAL_SEED, helpers::{check_program_account, check_program_id, check_stake_vault, check_token_account, global_pda, leave_registry, load_config, miner_pda, next_account}, state::{GlobalState, MinerAccount, ZeroCopy}};
 use pinocchio_token::instructions::{Transfer};
 use pinocchio::{seeds, instruction::Signer};
 
@@ -10,35 +9,65 @@ pub fn unstake_tokens(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64
     let miner_account = next_account(accounts_iter)?;
     let stake_vault = next_account(accounts_iter)?;
     let miner_token_account = next_account(accounts_iter)?;
-    let _token_program = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
     let global_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+    let registry_account = next_account(accounts_iter)?;
 
     if !miner.is_signer() {
         msg!("Miner must sign");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(ContractError::MissingSigner.into());
+    }
+    let config = load_config(program_id, config_account)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    // Only the miner's own account can be drawn down.
+    let (expected_miner, _) = miner_pda(program_id, miner.key());
+    if expected_miner != *miner_account.key() {
+        msg!("Miner account mismatch");
+        return Err(ContractError::MinerPdaMismatch.into());
+    }
+    check_program_account(program_id, miner_account, true)?;
+    check_stake_vault(program_id, stake_vault, miner.key())?;
+
+    let (global_pda, bump) = global_pda(program_id);
+    if *global_account.key() != global_pda {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    {
+        let global_data = global_account.try_borrow_data()?;
+        let global_state = GlobalState::load(&global_data)?;
+        check_token_account(miner_token_account, miner.key(), &global_state.reward_mint)?;
     }
 
     let mut miner_data = miner_account.try_borrow_mut_data()?;
-    let mut miner_acc = MinerAccount::try_from_slice(&miner_data).map_err(|_| {
-        msg!("Failed to deserialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
+    let miner_acc = MinerAccount::load_mut(&mut miner_data)?;
 
     let clock = Clock::get()?;
-    if clock.unix_timestamp < miner_acc.unstake_ts {
+    if clock.unix_timestamp < miner_acc.unstake_ts.get() {
         msg!("Cooldown period not elapsed");
-        return Err(ProgramError::Custom(4));
+        return Err(ContractError::CooldownActive.into());
     }
 
-    if miner_acc.stake < amount {
+    if miner_acc.stake.get() < amount {
         msg!("Insufficient stake");
-        return Err(ProgramError::InsufficientFunds);
+        return Err(ContractError::InsufficientStake.into());
     }
 
-    let (global_pda, bump) = global_pda(program_id);
-    if *global_account.key() != global_pda {
-        msg!("Global PDA mismatch");
-        return Err(ProgramError::InvalidArgument);
+    // Stake backing open epochs or held replicas stays at the minimum, so a
+    // miner cannot withdraw what a missed epoch or failed proof would slash.
+    if miner_acc.stake.get() - amount < config.min_assignment_stake {
+        if miner_acc.open_epochs.get() != 0 {
+            msg!("Miner still has open epochs");
+            return Err(ContractError::MinerHasOpenEpochs.into());
+        }
+        if miner_acc.assignments.get() != 0 {
+            msg!("Miner still holds assigned replicas");
+            return Err(ContractError::MinerHasAssignments.into());
+        }
     }
 
     let seed_bump = bump.clone();
@@ -54,12 +83,18 @@ pub fn unstake_tokens(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64
         amount,
     }.invoke_signed(&[signer])?;
 
-    miner_acc.stake = miner_acc.stake.saturating_sub(amount);
-    miner_acc.serialize(&mut &mut miner_data[..]).map_err(|_| {
-        msg!("Failed to serialize miner account");
-        ProgramError::InvalidAccountData
-    })?;
+    miner_acc.stake.set(miner_acc.stake.get().saturating_sub(amount));
 
-    msg!(&format!("EVENT:Unstaked:{:?}:{}", miner.key(), amount));
+    // A miner below the minimum can no longer be drawn and gives up its slot.
+    if miner_acc.stake.get() < config.min_assignment_stake {
+        leave_registry(program_id, registry_account, miner.key())?;
+    }
+
+    ContractEvent::Unstaked {
+        miner: *miner.key(),
+        amount,
+        stake: miner_acc.stake.get(),
+        timestamp: clock.unix_timestamp,
+    }.emit();
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,121 @@
+use pinocchio::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+use pinocchio_token::instructions::Transfer;
+
+use crate::{events::ContractEvent, constants::MAX_CHUNK_COUNT, error::ContractError, helpers::{check_program_account, check_program_id, escrow_pda, load_config, next_account, object_pda}, merkle, state::{ObjectRecord, PreviousVersion, ZeroCopy}};
+
+pub fn update_commitment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    object_id: u128,
+    commitment: [u8; 32],
+    size: u64,
+    chunk_count: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account(accounts_iter)?;
+    let object_account = next_account(accounts_iter)?;
+    let owner_token_account = next_account(accounts_iter)?;
+    let escrow_account = next_account(accounts_iter)?;
+    let token_program = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+
+    if !owner.is_signer() {
+        msg!("Owner must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+    let config = load_config(program_id, config_account)?;
+    check_program_id(token_program, &pinocchio_token::ID)?;
+
+    let (expected_escrow, _) = escrow_pda(program_id, object_account.key());
+    if expected_escrow != *escrow_account.key() {
+        msg!("Escrow PDA mismatch");
+        return Err(ContractError::EscrowPdaMismatch.into());
+    }
+
+    if chunk_count != merkle::chunk_count(size) {
+        msg!("Chunk count does not match object size");
+        return Err(ContractError::InvalidChunkCount.into());
+    }
+
+    if chunk_count > MAX_CHUNK_COUNT {
+        msg!("Object exceeds the maximum chunk count");
+        return Err(ContractError::InvalidChunkCount.into());
+    }
+
+    check_program_account(program_id, object_account, true)?;
+    let mut object_data = object_account.try_borrow_mut_data()?;
+    let object = ObjectRecord::load_mut(&mut object_data)?;
+
+    let (expected_object, _) = object_pda(program_id, &object.creator, object.object_id.get());
+    if expected_object != *object_account.key() || object.object_id.get() != object_id {
+        msg!("Object PDA mismatch");
+        return Err(ContractError::ObjectPdaMismatch.into());
+    }
+
+    if object.owner != *owner.key() {
+        msg!("Signer is not the object owner");
+        return Err(ContractError::NotObjectOwner.into());
+    }
+
+    // Epochs opened before the update are still proven against the content
+    // they were opened for, so it is kept until they complete. Epochs not yet
+    // opened are repriced below and prove the new content, so a replaced
+    // version's paid retention ends with its last opened epoch. An update that
+    // would drop a version still being proven is rejected.
+    let history_slot = object
+        .previous_versions
+        .iter()
+        .position(|previous| previous.open_epochs.get() == 0)
+        .ok_or_else(|| {
+            msg!("Every previous version still has open epochs");
+            ProgramError::from(ContractError::PreviousVersionInUse)
+        })?;
+
+    // Epochs already opened keep their payment; the epochs still to be opened
+    // are repriced for the new size, topping up or freeing escrow.
+    let remaining_epochs: u64 = object
+        .active_slots()
+        .map(|slot| object.retention_epochs.get().saturating_sub(object.epochs_opened[slot].get()))
+        .sum();
+    let epoch_payment = chunk_count.checked_mul(config.price_per_chunk_epoch).ok_or(ProgramError::ArithmeticOverflow)?;
+    let old_cost = object.epoch_payment.get().checked_mul(remaining_epochs).ok_or(ProgramError::ArithmeticOverflow)?;
+    let new_cost = epoch_payment.checked_mul(remaining_epochs).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let top_up = new_cost.saturating_sub(old_cost);
+    let freed = old_cost.saturating_sub(new_cost);
+    object.deposit.set(object.deposit.get().checked_add(top_up).ok_or(ProgramError::ArithmeticOverflow)?.saturating_sub(freed));
+    object.refundable.set(object.refundable.get().saturating_add(freed));
+
+    let previous_open: u64 = object.previous_versions.iter().map(|previous| previous.open_epochs.get()).sum();
+    object.previous_versions[history_slot] = PreviousVersion {
+        version: object.version,
+        commitment: object.commitment,
+        size: object.size,
+        chunk_count: object.chunk_count,
+        open_epochs: object.open_epochs().saturating_sub(previous_open).into(),
+    };
+    object.version.set(object.version.get().checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?);
+    object.commitment = commitment;
+    object.size.set(size);
+    object.chunk_count.set(chunk_count);
+    object.epoch_payment.set(epoch_payment);
+
+    if top_up > 0 {
+        Transfer {
+            from: owner_token_account,
+            to: escrow_account,
+            authority: owner,
+            amount: top_up,
+        }.invoke()?;
+    }
+
+    ContractEvent::CommitmentUpdated {
+        object_id,
+        version: object.version.get(),
+        commitment,
+        top_up,
+        freed,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, ProgramResult};
+
+use crate::{events::ContractEvent, error::ContractError, helpers::{check_admin_quorum, check_program_account, check_writable, config_pda, global_pda, next_account}, state::{Config, ConfigParams, GlobalState, ProgramAccount, ZeroCopy}};
+
+/// Replaces the protocol parameters. Approved like SetAdmins: the first
+/// account and any further admin signers after the config account.
+pub fn update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: ConfigParams,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let approver = next_account(accounts_iter)?;
+    let global_account = next_account(accounts_iter)?;
+    let config_account = next_account(accounts_iter)?;
+
+    if !approver.is_signer() {
+        msg!("Admin must sign");
+        return Err(ContractError::MissingSigner.into());
+    }
+
+    let (expected_global, _) = global_pda(program_id);
+    if expected_global != *global_account.key() {
+        msg!("Global PDA mismatch");
+        return Err(ContractError::GlobalPdaMismatch.into());
+    }
+    check_program_account(program_id, global_account, false)?;
+
+    let (expected_config, _) = config_pda(program_id);
+    if expected_config != *config_account.key() {
+        msg!("Config PDA mismatch");
+        return Err(ContractError::ConfigPdaMismatch.into());
+    }
+    check_program_account(program_id, config_account, true)?;
+
+    if !params.is_valid() {
+        msg!("Config parameters out of bounds");
+        return Err(ContractError::InvalidConfig.into());
+    }
+
+    let mut global_data = global_account.try_borrow_mut_data()?;
+    let global_state = GlobalState::load_mut(&mut global_data)?;
+
+    check_admin_quorum(global_state, core::iter::once(approver).chain(accounts_iter))?;
+
+    let mut config_data = config_account.try_borrow_mut_data()?;
+    let mut config = Config::load(&config_data)?;
+
+    // A new base reward restarts the emission schedule from it.
+    let clock = Clock::get()?;
+    if params.base_epoch_reward != config.params.base_epoch_reward {
+        check_writable(global_account)?;
+        global_state.epoch_reward.set(params.base_epoch_reward);
+        global_state.last_decay_at.set(clock.unix_timestamp);
+    }
+
+    config.params = params.clone();
+    config.store(&mut config_data)?;
+
+    ContractEvent::ConfigUpdated {
+        params,
+        timestamp: clock.unix_timestamp,
+    }.emit();
+    Ok(())
+}
@@ -0,0 +1,169 @@
+//! Layouts the first deployment wrote: header-less Borsh bodies with no
+//! discriminator or version. They are only read by Migrate, which converts
+//! them to the zero-copy layouts in state.rs.
+
+use borsh::BorshDeserialize;
+use pinocchio::pubkey::Pubkey;
+
+use crate::constants::{BASE_EPOCH_REWARD, CHALLENGES_PER_EPOCH, EPOCH_SEED, MAX_ADMINS, MAX_REPLICATION, MINER_METADATA_LEN, VERSION_HISTORY};
+use crate::helpers::find_pda;
+use crate::merkle;
+use crate::state::{EmissionMode, EpochRecord, EpochStatus, GlobalState, MinerAccount, ObjectRecord, PreviousVersion, ProofType};
+
+#[derive(BorshDeserialize)]
+pub struct GlobalStateV0 {
+    pub admin: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_vault: Pubkey,
+    pub total_minted: u64,
+    pub emission_cap: u64,
+    pub decay_numerator: u64,
+    pub decay_denom: u64,
+    pub last_decay_at: i64,
+}
+
+/// Seeded by the owner and an id the record does not store.
+#[derive(BorshDeserialize)]
+pub struct ObjectRecordV0 {
+    pub owner: Pubkey,
+    pub commitment: [u8; 32],
+    pub proof_type: ProofType,
+    pub size: u64,
+    pub created_ts: i64,
+    pub retention_epochs: u64,
+    pub bump: u8,
+}
+
+/// Seeded by object id and epoch id alone, see `epoch_pda_v0`. Only the
+/// first four statuses existed. The trailing bump is left unread, as the
+/// record moves to a new account.
+#[derive(BorshDeserialize)]
+pub struct EpochRecordV0 {
+    pub object_id: u128,
+    pub epoch_id: u128,
+    pub nonce: u64,
+    pub deadline_ts: i64,
+    pub solver: Option<Pubkey>,
+    pub proof_hash: [u8; 32],
+    pub status: EpochStatus,
+    pub reward: u64,
+}
+
+#[derive(BorshDeserialize)]
+pub struct MinerAccountV0 {
+    pub miner: Pubkey,
+    pub stake: u64,
+    pub pending_rewards: u64,
+    pub reputation: u32,
+    pub unstake_ts: i64,
+    pub bump: u8,
+}
+
+pub fn epoch_pda_v0(program_id: &Pubkey, object_id: u128, epoch_id: u128) -> (Pubkey, u8) {
+    let oid_bytes = object_id.to_le_bytes();
+    let eid_bytes = epoch_id.to_le_bytes();
+    find_pda(&[EPOCH_SEED, &oid_bytes, &eid_bytes], program_id)
+}
+
+impl From<GlobalStateV0> for GlobalState {
+    fn from(v0: GlobalStateV0) -> Self {
+        let mut admins = [Pubkey::default(); MAX_ADMINS];
+        admins[0] = v0.admin;
+        GlobalState {
+            admin: v0.admin,
+            reward_mint: v0.reward_mint,
+            reward_vault: v0.reward_vault,
+            // Rewards were paid out of the vault as epochs were finalised, so
+            // everything allocated was also paid.
+            emission_mode: EmissionMode::Vault as u8,
+            total_minted: v0.total_minted.into(),
+            total_allocated: v0.total_minted.into(),
+            emission_cap: v0.emission_cap.into(),
+            decay_numerator: v0.decay_numerator.into(),
+            decay_denom: v0.decay_denom.into(),
+            last_decay_at: v0.last_decay_at.into(),
+            epoch_reward: BASE_EPOCH_REWARD.into(),
+            pending_admin: None.into(),
+            paused: 0.into(),
+            admins,
+            admin_count: 1,
+            admin_threshold: 1,
+        }
+    }
+}
+
+impl ObjectRecordV0 {
+    /// Objects had no assigned miners and no escrow, so the record comes
+    /// across with no replicas and nothing prepaid. It has expired as far as
+    /// CloseObject is concerned.
+    pub fn upgrade(self, object_id: u128) -> ObjectRecord {
+        ObjectRecord {
+            owner: self.owner,
+            creator: self.owner,
+            object_id: object_id.into(),
+            commitment: self.commitment,
+            proof_type: self.proof_type.into(),
+            size: self.size.into(),
+            chunk_count: merkle::chunk_count(self.size).into(),
+            created_ts: self.created_ts.into(),
+            retention_epochs: self.retention_epochs.into(),
+            replication_factor: 0,
+            assigned_miners: [Pubkey::default(); MAX_REPLICATION],
+            epoch_payment: 0.into(),
+            deposit: 0.into(),
+            epochs_opened: [0.into(); MAX_REPLICATION],
+            epochs_completed: [0.into(); MAX_REPLICATION],
+            next_epoch_ts: [self.created_ts.into(); MAX_REPLICATION],
+            refundable: 0.into(),
+            version: 0.into(),
+            previous_versions: [PreviousVersion::default(); VERSION_HISTORY],
+            pending_owner: None.into(),
+            bump: self.bump,
+        }
+    }
+}
+
+impl EpochRecordV0 {
+    /// A submitted epoch, rebuilt for the account seeded by `object` and its
+    /// solver, with `opener` paying its rent. It pays nothing from escrow,
+    /// and its dispute window runs from the old deadline.
+    pub fn upgrade(self, object: Pubkey, solver: Pubkey, opener: Pubkey, bump: u8) -> EpochRecord {
+        EpochRecord {
+            object_id: self.object_id.into(),
+            object,
+            epoch_id: self.epoch_id.into(),
+            nonce: self.nonce.into(),
+            seed_slot: 0.into(),
+            deadline_ts: self.deadline_ts.into(),
+            challenges: [0.into(); CHALLENGES_PER_EPOCH],
+            miner: solver,
+            solver: Some(solver).into(),
+            proof_hash: self.proof_hash,
+            submitted_at: self.deadline_ts.into(),
+            status: self.status as u8,
+            proven: 0,
+            reward: self.reward.into(),
+            payment: 0.into(),
+            version: 0.into(),
+            opener,
+            bump,
+        }
+    }
+}
+
+impl From<MinerAccountV0> for MinerAccount {
+    fn from(v0: MinerAccountV0) -> Self {
+        MinerAccount {
+            miner: v0.miner,
+            operator: v0.miner,
+            stake: v0.stake.into(),
+            pending_rewards: v0.pending_rewards.into(),
+            reputation: v0.reputation.into(),
+            unstake_ts: v0.unstake_ts.into(),
+            metadata: [0u8; MINER_METADATA_LEN],
+            open_epochs: 0.into(),
+            assignments: 0.into(),
+            bump: v0.bump,
+        }
+    }
+}
@@ -2,15 +2,25 @@
 
 use borsh::BorshDeserialize;
 use pinocchio::{
-  account_info::AccountInfo, entrypoint, msg, pubkey::Pubkey, ProgramResult
+  account_info::AccountInfo, msg, pubkey::Pubkey, ProgramResult
 };
 use crate::{state::RewardInstruction};
 mod state;
 mod instructions;
 mod helpers;
 mod constants;
+pub mod error;
+pub mod events;
+mod merkle;
+mod pod;
+mod legacy;
 
-entrypoint!(process_instruction);
+pub use state::ConfigParams;
+// Zero-copy layouts, for clients decoding accounts or building memcmp filters.
+pub use state::{ACCOUNT_HEADER_LEN, EpochRecord, GlobalState, MinerAccount, ObjectRecord};
+
+#[cfg(not(feature = "no-entrypoint"))]
+pinocchio::entrypoint!(process_instruction);
 
 pub fn process_instruction(
   program_id: &Pubkey,
@@ -24,33 +34,42 @@ pub fn process_instruction(
     pinocchio::program_error::ProgramError::InvalidInstructionData
   })?;
 
+  // Every instruction except Initialize and governance honours the pause mask.
+  if let Some(bit) = instructions.pause_bit() {
+    helpers::check_not_paused(program_id, accounts, bit)?;
+  }
+
   match instructions {
     RewardInstruction::Initialize {
       decay_numerator,
       decay_denom,
       emission_cap,
-    } => instructions::initialize(program_id, accounts, decay_numerator, decay_denom, emission_cap),
+      emission_mode,
+    } => instructions::initialize(program_id, accounts, decay_numerator, decay_denom, emission_cap, emission_mode),
 
     RewardInstruction::RegisterObject {
       commitment,
       proof_type,
       size,
+      chunk_count,
       retention_epochs,
       object_id,
-    } => instructions::register_object(program_id, accounts, commitment, proof_type, size, retention_epochs, object_id),
+      replication_factor,
+    } => instructions::register_object(program_id, accounts, commitment, proof_type, size, chunk_count, retention_epochs, object_id, replication_factor),
 
-    RewardInstruction::CreateEpoch { object_id, nonce, epoch_id } =>
-      instructions::create_epoch(program_id, accounts, object_id, nonce, epoch_id),
+    RewardInstruction::CreateEpoch { object_id } =>
+      instructions::create_epoch(program_id, accounts, object_id),
 
     RewardInstruction::SubmitProof {
       epoch_id,
       proof_hash,
-    } => instructions::submit_proof(program_id, accounts, epoch_id, proof_hash),
+      proofs,
+    } => instructions::submit_proof(program_id, accounts, epoch_id, proof_hash, proofs),
 
     RewardInstruction::ChallengeProof {
        epoch_id,
-       evidence_hash,
-    } => instructions::challenge_proof(program_id, accounts, epoch_id, evidence_hash),
+       chunk_index,
+    } => instructions::challenge_proof(program_id, accounts, epoch_id, chunk_index),
 
     RewardInstruction::FinalizeEpoch { epoch_id } => instructions::finalize_epoch(program_id, accounts, epoch_id),
 
@@ -66,5 +85,68 @@ pub fn process_instruction(
       miner,
       amount,
     } => instructions::slash_miner(program_id, accounts, miner, amount),
+
+    RewardInstruction::RegisterMiner {
+      operator,
+      metadata,
+    } => instructions::register_miner(program_id, accounts, operator, metadata),
+
+    RewardInstruction::DeregisterMiner => instructions::deregister_miner(program_id, accounts),
+
+    RewardInstruction::ClaimRewards => instructions::claim_rewards(program_id, accounts),
+
+    RewardInstruction::ResolveChallenge { epoch_id } => instructions::resolve_challenge(program_id, accounts, epoch_id),
+
+    RewardInstruction::MarkMissed { epoch_id } => instructions::mark_missed(program_id, accounts, epoch_id),
+
+    RewardInstruction::RefundEscrow { object_id } => instructions::refund_escrow(program_id, accounts, object_id),
+
+    RewardInstruction::CloseObject { object_id } => instructions::close_object(program_id, accounts, object_id),
+
+    RewardInstruction::CloseEpoch { epoch_id } => instructions::close_epoch(program_id, accounts, epoch_id),
+
+    RewardInstruction::ExtendRetention {
+      object_id,
+      additional_epochs,
+    } => instructions::extend_retention(program_id, accounts, object_id, additional_epochs),
+
+    RewardInstruction::UpdateCommitment {
+      object_id,
+      commitment,
+      size,
+      chunk_count,
+    } => instructions::update_commitment(program_id, accounts, object_id, commitment, size, chunk_count),
+
+    RewardInstruction::TransferObject {
+      object_id,
+      new_owner,
+      require_accept,
+    } => instructions::transfer_object(program_id, accounts, object_id, new_owner, require_accept),
+
+    RewardInstruction::AcceptObject { object_id } => instructions::accept_object(program_id, accounts, object_id),
+
+    RewardInstruction::ReleaseReplica { object_id, miner } => instructions::release_replica(program_id, accounts, object_id, miner),
+
+    RewardInstruction::ProposeAdmin { new_admin } => instructions::propose_admin(program_id, accounts, new_admin),
+
+    RewardInstruction::AcceptAdmin => instructions::accept_admin(program_id, accounts),
+
+    RewardInstruction::SetPause { paused } => instructions::set_pause(program_id, accounts, paused),
+
+    RewardInstruction::SetAdmins {
+      admins,
+      threshold,
+    } => instructions::set_admins(program_id, accounts, admins, threshold),
+
+    RewardInstruction::UpdateConfig { params } => instructions::update_config(program_id, accounts, params),
+
+    RewardInstruction::Migrate { kind } => instructions::migrate(program_id, accounts, kind),
+
+    RewardInstruction::MigrateStake { miner } => instructions::migrate_stake(program_id, accounts, miner),
+
+    RewardInstruction::AnswerChallenge {
+      epoch_id,
+      proof,
+    } => instructions::answer_challenge(program_id, accounts, epoch_id, proof),
   }
 }
\ No newline at end of file
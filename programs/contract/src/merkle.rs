@@ -0,0 +1,96 @@
+use solana_sha256_hasher::hashv;
+
+use crate::{constants::{CHALLENGES_PER_EPOCH, CHUNK_SIZE}, state::ChunkProof};
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn hash_leaf(chunk: &[u8]) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, chunk]).to_bytes()
+}
+
+pub fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Number of chunks an object of `size` bytes is split into.
+pub fn chunk_count(size: u64) -> u64 {
+    size.div_ceil(CHUNK_SIZE).max(1)
+}
+
+/// Length of chunk `index`; only the last chunk may be shorter than `CHUNK_SIZE`.
+pub fn chunk_len(size: u64, index: u64) -> u64 {
+    size.saturating_sub(index * CHUNK_SIZE).min(CHUNK_SIZE)
+}
+
+/// Height of a tree over `leaves` leaves. An odd node at any level is paired with itself.
+pub fn tree_depth(leaves: u64) -> usize {
+    leaves.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Chunk indices challenged for an epoch, derived from the epoch nonce.
+pub fn challenge_indices(nonce: u64, epoch_id: u128, leaves: u64) -> [u64; CHALLENGES_PER_EPOCH] {
+    let mut indices = [0u64; CHALLENGES_PER_EPOCH];
+    for (i, index) in indices.iter_mut().enumerate() {
+        let digest = hashv(&[&nonce.to_le_bytes(), &epoch_id.to_le_bytes(), &[i as u8]]).to_bytes();
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&digest[..8]);
+        *index = u64::from_le_bytes(word) % leaves;
+    }
+    indices
+}
+
+/// Start of the digest binding the challenged chunks to the epoch nonce,
+/// which is what a miner commits to as `proof_hash`.
+pub fn response_seed(nonce: u64) -> [u8; 32] {
+    hashv(&[&nonce.to_le_bytes()]).to_bytes()
+}
+
+/// Folds proven chunks into the response digest in challenge order, so they
+/// can arrive over several instructions.
+pub fn fold_response(mut acc: [u8; 32], proofs: &[ChunkProof]) -> [u8; 32] {
+    for proof in proofs {
+        acc = hashv(&[&acc, &proof.chunk]).to_bytes();
+    }
+    acc
+}
+
+/// Checks that `leaf` sits at `index` in a tree of `leaves` leaves with the given root.
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], index: u64, leaves: u64, path: &[[u8; 32]]) -> bool {
+    if index >= leaves || path.len() != tree_depth(leaves) {
+        return false;
+    }
+
+    let mut node = *leaf;
+    let mut idx = index;
+    for sibling in path {
+        node = if idx % 2 == 0 {
+            hash_node(&node, sibling)
+        } else {
+            hash_node(sibling, &node)
+        };
+        idx /= 2;
+    }
+    node == *root
+}
+
+/// Verifies one chunk proof per challenged index against the object commitment.
+pub fn verify_chunks(
+    root: &[u8; 32],
+    size: u64,
+    leaves: u64,
+    challenges: &[u64],
+    proofs: &[ChunkProof],
+) -> bool {
+    if proofs.len() != challenges.len() {
+        return false;
+    }
+
+    challenges.iter().zip(proofs).all(|(&index, proof)| verify_chunk(root, size, leaves, index, proof))
+}
+
+/// Verifies a proof of chunk `index` against the object commitment.
+pub fn verify_chunk(root: &[u8; 32], size: u64, leaves: u64, index: u64, proof: &ChunkProof) -> bool {
+    proof.chunk.len() as u64 == chunk_len(size, index)
+        && verify(root, &hash_leaf(&proof.chunk), index, leaves, &proof.path)
+}
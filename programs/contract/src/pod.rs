@@ -0,0 +1,67 @@
+//! Little-endian integers and optional keys with alignment 1, for the
+//! zero-copy account layouts in state.rs. Account bodies start after the
+//! 9-byte header, so every field is a byte array converted on access.
+
+use pinocchio::pubkey::Pubkey;
+
+macro_rules! pod_int {
+    ($name:ident, $ty:ty) => {
+        #[repr(transparent)]
+        #[derive(Clone, Copy, Default, Debug, PartialEq)]
+        pub struct $name([u8; size_of::<$ty>()]);
+
+        impl $name {
+            #[inline(always)]
+            pub fn get(&self) -> $ty {
+                <$ty>::from_le_bytes(self.0)
+            }
+
+            #[inline(always)]
+            pub fn set(&mut self, value: $ty) {
+                self.0 = value.to_le_bytes();
+            }
+        }
+
+        impl From<$ty> for $name {
+            #[inline(always)]
+            fn from(value: $ty) -> Self {
+                Self(value.to_le_bytes())
+            }
+        }
+    };
+}
+
+pod_int!(PodU32, u32);
+pod_int!(PodU64, u64);
+pod_int!(PodI64, i64);
+pod_int!(PodU128, u128);
+
+/// `Option<Pubkey>` as a presence flag followed by the key, so it always
+/// takes 33 bytes.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct PodOptionPubkey {
+    is_some: u8,
+    key: Pubkey,
+}
+
+impl PodOptionPubkey {
+    #[inline(always)]
+    pub fn get(&self) -> Option<Pubkey> {
+        (self.is_some != 0).then_some(self.key)
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, value: Option<Pubkey>) {
+        *self = value.into();
+    }
+}
+
+impl From<Option<Pubkey>> for PodOptionPubkey {
+    fn from(value: Option<Pubkey>) -> Self {
+        match value {
+            Some(key) => Self { is_some: 1, key },
+            None => Self::default(),
+        }
+    }
+}
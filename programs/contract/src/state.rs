@@ -1,93 +1,684 @@
+use core::mem::offset_of;
+
 use borsh::{BorshSerialize, BorshDeserialize};
-use pinocchio::pubkey::Pubkey;
+use pinocchio::{msg, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+use crate::pod::{PodI64, PodOptionPubkey, PodU128, PodU32, PodU64};
+
+use crate::constants::{
+    BASE_EPOCH_REWARD, CHALLENGER_SHARE_BPS, CHALLENGES_PER_EPOCH, CHALLENGE_BOND, CHALLENGE_REPUTATION_PENALTY, DECAY_PERIOD_SECS,
+    DISPUTE_WINDOW_SECS, EPOCH_DURATION_SECS, INITIAL_REPUTATION, MAX_ADMINS, MAX_DECAY_STEPS, MAX_MINERS, MAX_REPLICATION, MINER_METADATA_LEN,
+    MIN_ASSIGNMENT_STAKE, MISSED_REPUTATION_PENALTY, MISSED_SLASH_BPS, PRICE_PER_CHUNK_EPOCH, SLASH_BPS, SLASH_REPUTATION_PENALTY,
+    UNSTAKE_COOLDOWN_SECS, VERSION_HISTORY,
+};
+use crate::error::ContractError;
+
+/// Every program account starts with an 8-byte discriminator,
+/// `sha256("account:<Type>")[..8]`, and a layout version, followed by the
+/// body: Borsh for `ProgramAccount`s, a fixed `repr(C)` layout for
+/// `ZeroCopy` ones.
+pub const ACCOUNT_HEADER_LEN: usize = 8 + 1;
+
+/// Rejects other account types and outdated layouts.
+fn check_header(data: &[u8], discriminator: &[u8; 8], version: u8) -> ProgramResult {
+    if data.len() < ACCOUNT_HEADER_LEN || data[..8] != *discriminator {
+        msg!("Account discriminator mismatch");
+        return Err(ContractError::AccountDiscriminatorMismatch.into());
+    }
+    if data[8] != version {
+        msg!("Account layout is outdated, migrate it first");
+        return Err(ContractError::AccountVersionMismatch.into());
+    }
+    Ok(())
+}
+
+pub trait ProgramAccount: BorshSerialize + BorshDeserialize {
+    const DISCRIMINATOR: [u8; 8];
+    /// Bumped whenever the body layout changes.
+    const VERSION: u8;
+    /// Largest body, excluding the header.
+    const LEN: usize;
+    const SPACE: usize = ACCOUNT_HEADER_LEN + Self::LEN;
+
+    /// Reads an account, rejecting other account types and outdated layouts.
+    fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        check_header(data, &Self::DISCRIMINATOR, Self::VERSION)?;
+        Self::deserialize(&mut &data[ACCOUNT_HEADER_LEN..]).map_err(|_| {
+            msg!("Failed to deserialize account");
+            ProgramError::InvalidAccountData
+        })
+    }
+
+    fn store(&self, data: &mut [u8]) -> ProgramResult {
+        if data.len() < ACCOUNT_HEADER_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        data[..8].copy_from_slice(&Self::DISCRIMINATOR);
+        data[8] = Self::VERSION;
+        self.serialize(&mut &mut data[ACCOUNT_HEADER_LEN..]).map_err(|_| {
+            msg!("Failed to serialize account");
+            ProgramError::AccountDataTooSmall
+        })
+    }
+}
+
+/// Account read and updated in place in the account data, without a
+/// deserialise/serialise round trip. Fields sit at fixed offsets, so clients
+/// can filter on them with `memcmp`.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` and built only from byte arrays (see
+/// pod.rs), so they have alignment 1 and every bit pattern is a valid value.
+pub unsafe trait ZeroCopy: Sized {
+    const DISCRIMINATOR: [u8; 8];
+    /// Bumped whenever the body layout changes. Accounts that predate the
+    /// headers hold a Borsh body (see legacy.rs), which Migrate converts.
+    const VERSION: u8;
+    const LEN: usize = size_of::<Self>();
+    const SPACE: usize = ACCOUNT_HEADER_LEN + Self::LEN;
+
+    fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        check_header(data, &Self::DISCRIMINATOR, Self::VERSION)?;
+        let body = data.get(ACCOUNT_HEADER_LEN..Self::SPACE).ok_or(ProgramError::AccountDataTooSmall)?;
+        // SAFETY: `body` is `LEN` bytes and `Self` has alignment 1.
+        Ok(unsafe { &*(body.as_ptr() as *const Self) })
+    }
+
+    fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        check_header(data, &Self::DISCRIMINATOR, Self::VERSION)?;
+        let body = data.get_mut(ACCOUNT_HEADER_LEN..Self::SPACE).ok_or(ProgramError::AccountDataTooSmall)?;
+        // SAFETY: as in `load`, and `body` is borrowed mutably.
+        Ok(unsafe { &mut *(body.as_mut_ptr() as *mut Self) })
+    }
+
+    /// Writes the header and the whole body, for new and migrated accounts.
+    fn store(&self, data: &mut [u8]) -> ProgramResult {
+        if data.len() < Self::SPACE {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        data[..8].copy_from_slice(&Self::DISCRIMINATOR);
+        data[8] = Self::VERSION;
+        // SAFETY: `Self` is plain bytes without padding.
+        let body = unsafe { core::slice::from_raw_parts(self as *const Self as *const u8, Self::LEN) };
+        data[ACCOUNT_HEADER_LEN..Self::SPACE].copy_from_slice(body);
+        Ok(())
+    }
+}
+
+#[repr(C)]
 pub struct GlobalState {
     pub admin: Pubkey,
     pub reward_mint: Pubkey,
     pub reward_vault: Pubkey, 
-    pub total_minted: u64,
-    pub emission_cap: u64, // remaining cap / current cap
-    pub decay_numerator: u64, // represent decay as fraction (numerator/denom)
-    pub decay_denom: u64,
-    pub last_decay_at: i64,
+    pub emission_mode: u8, // EmissionMode, see emission_mode()
+    pub total_minted: PodU64, // tokens actually minted by the program
+    pub total_allocated: PodU64, // rewards credited to miners; never exceeds emission_cap
+    pub emission_cap: PodU64,
+    pub decay_numerator: PodU64, // represent decay as fraction (numerator/denom)
+    pub decay_denom: PodU64,
+    pub last_decay_at: PodI64,
+    pub epoch_reward: PodU64, // current per-epoch emission
+    pub pending_admin: PodOptionPubkey, // proposed by ProposeAdmin until accepted
+    pub paused: PodU64, // bit RewardInstruction::pause_bit() set = instruction halted
+    pub admins: [Pubkey; MAX_ADMINS], // first admin_count entries are set
+    pub admin_count: u8,
+    pub admin_threshold: u8, // approvals needed for Slash and SetAdmins
+}
+
+impl GlobalState {
+    pub fn emission_mode(&self) -> Result<EmissionMode, ProgramError> {
+        self.emission_mode.try_into()
+    }
+
+    /// Applies one decay step per elapsed `period` seconds. Steps are capped
+    /// per call so a long idle period is caught up over several calls.
+    pub fn apply_decay(&mut self, now: i64, period: i64) {
+        let last_decay_at = self.last_decay_at.get();
+        let periods = (now.saturating_sub(last_decay_at) / period).min(MAX_DECAY_STEPS);
+        if periods <= 0 {
+            return;
+        }
+
+        let mut epoch_reward = self.epoch_reward.get();
+        for _ in 0..periods {
+            let decayed = (epoch_reward as u128 * self.decay_numerator.get() as u128 / self.decay_denom.get() as u128) as u64;
+            epoch_reward = epoch_reward.saturating_sub(decayed);
+        }
+        self.epoch_reward.set(epoch_reward);
+        self.last_decay_at.set(last_decay_at + periods * period);
+    }
+
+    pub fn remaining_emission(&self) -> u64 {
+        self.emission_cap.get().saturating_sub(self.total_allocated.get())
+    }
+
+    /// Allocates up to `reward` under the emission cap and returns the amount
+    /// actually allocated.
+    pub fn allocate(&mut self, reward: u64) -> u64 {
+        let amount = reward.min(self.remaining_emission());
+        self.total_allocated.set(self.total_allocated.get().saturating_add(amount));
+        amount
+    }
+
+    pub fn is_paused(&self, bit: u32) -> bool {
+        self.paused.get() & (1u64 << bit) != 0
+    }
+
+    /// Position of `key` in the admin set, if it is a member.
+    pub fn admin_slot(&self, key: &Pubkey) -> Option<usize> {
+        self.admins[..self.admin_count as usize].iter().position(|a| a == key)
+    }
+
+    /// Hands `from`'s seat in the admin set to `to`. If `to` already holds a
+    /// seat, `from`'s is dropped instead. Returns false if `from` holds no
+    /// seat, or if dropping it would leave fewer admins than the threshold.
+    pub fn replace_admin(&mut self, from: &Pubkey, to: &Pubkey) -> bool {
+        let Some(slot) = self.admin_slot(from) else {
+            return false;
+        };
+        if self.admin_slot(to).is_none() {
+            self.admins[slot] = *to;
+            return true;
+        }
+        let last = self.admin_count as usize - 1;
+        if last < self.admin_threshold as usize {
+            return false;
+        }
+        self.admins[slot] = self.admins[last];
+        self.admins[last] = Pubkey::default();
+        self.admin_count -= 1;
+        true
+    }
+}
+
+unsafe impl ZeroCopy for GlobalState {
+    const DISCRIMINATOR: [u8; 8] = [163, 46, 74, 168, 216, 123, 133, 98];
+    const VERSION: u8 = 2;
+}
+
+/// Protocol parameters held by the Config PDA. See constants.rs for the
+/// defaults and what each one governs.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct ConfigParams {
+    pub epoch_duration_secs: i64,
+    pub unstake_cooldown_secs: i64,
+    pub base_epoch_reward: u64,
+    pub decay_period_secs: i64,
+    pub price_per_chunk_epoch: u64,
+    pub min_assignment_stake: u64,
+    pub challenge_bond: u64,
+    pub dispute_window_secs: i64,
+    pub slash_bps: u64,
+    pub challenger_share_bps: u64,
+    pub missed_slash_bps: u64,
+    pub challenge_reputation_penalty: u32,
+    pub slash_reputation_penalty: u32,
+    pub missed_reputation_penalty: u32,
+}
+
+impl Default for ConfigParams {
+    fn default() -> Self {
+        ConfigParams {
+            epoch_duration_secs: EPOCH_DURATION_SECS,
+            unstake_cooldown_secs: UNSTAKE_COOLDOWN_SECS,
+            base_epoch_reward: BASE_EPOCH_REWARD,
+            decay_period_secs: DECAY_PERIOD_SECS,
+            price_per_chunk_epoch: PRICE_PER_CHUNK_EPOCH,
+            min_assignment_stake: MIN_ASSIGNMENT_STAKE,
+            challenge_bond: CHALLENGE_BOND,
+            dispute_window_secs: DISPUTE_WINDOW_SECS,
+            slash_bps: SLASH_BPS,
+            challenger_share_bps: CHALLENGER_SHARE_BPS,
+            missed_slash_bps: MISSED_SLASH_BPS,
+            challenge_reputation_penalty: CHALLENGE_REPUTATION_PENALTY,
+            slash_reputation_penalty: SLASH_REPUTATION_PENALTY,
+            missed_reputation_penalty: MISSED_REPUTATION_PENALTY,
+        }
+    }
+}
+
+impl ConfigParams {
+    // 4 i64 durations, 7 u64 amounts and rates, 3 u32 penalties
+    pub const LEN: usize = 4 * 8 + 7 * 8 + 3 * 4;
+
+    /// Bounds UpdateConfig enforces. Durations stay within a day (a month
+    /// for the cooldown and a year for decay), rates within 100%, and stakes,
+    /// prices and bonds non-zero so assignment and challenges keep working.
+    pub fn is_valid(&self) -> bool {
+        const DAY: i64 = 86_400;
+        (10..=DAY).contains(&self.epoch_duration_secs)
+            && (0..=30 * DAY).contains(&self.unstake_cooldown_secs)
+            && self.base_epoch_reward > 0
+            && (60..=365 * DAY).contains(&self.decay_period_secs)
+            && self.price_per_chunk_epoch > 0
+            && self.min_assignment_stake > 0
+            && self.challenge_bond > 0
+            && (60..=DAY).contains(&self.dispute_window_secs)
+            && self.slash_bps <= 10_000
+            && self.challenger_share_bps <= 10_000
+            && self.missed_slash_bps <= 10_000
+            && self.challenge_reputation_penalty <= INITIAL_REPUTATION
+            && self.slash_reputation_penalty <= INITIAL_REPUTATION
+            && self.missed_reputation_penalty <= INITIAL_REPUTATION
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Config {
+    pub params: ConfigParams,
+    pub bump: u8,
+}
+
+impl ProgramAccount for Config {
+    const DISCRIMINATOR: [u8; 8] = [155, 12, 170, 224, 30, 250, 204, 130];
+    const VERSION: u8 = 1;
+    const LEN: usize = ConfigParams::LEN + 1;
+}
+
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum EmissionMode {
+    Vault, // rewards are paid from a pre-funded reward_vault
+    Mint,  // rewards are minted on claim by the global PDA
+}
+
+impl TryFrom<u8> for EmissionMode {
+    type Error = ProgramError;
+
+    /// Fails on a byte the program never writes, so a corrupt account is
+    /// rejected instead of aborting the program.
+    fn try_from(mode: u8) -> Result<Self, ProgramError> {
+        match mode {
+            0 => Ok(EmissionMode::Vault),
+            1 => Ok(EmissionMode::Mint),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
 pub enum ProofType {
     CompactHash,
     Snark, // SNARK/STARK style compact proofs
     Other(u8),
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+impl From<u8> for ProofType {
+    fn from(proof_type: u8) -> Self {
+        match proof_type {
+            0 => ProofType::CompactHash,
+            1 => ProofType::Snark,
+            other => ProofType::Other(other),
+        }
+    }
+}
+
+impl From<ProofType> for u8 {
+    fn from(proof_type: ProofType) -> Self {
+        match proof_type {
+            ProofType::CompactHash => 0,
+            ProofType::Snark => 1,
+            ProofType::Other(other) => other,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
 pub enum EpochStatus {
     Open,
     Submitted,
     Challenged,
     Finalized,
+    Slashed, // a challenge showed the submitted proof was wrong
+    Missed,  // no proof before deadline_ts
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+impl TryFrom<u8> for EpochStatus {
+    type Error = ProgramError;
+
+    fn try_from(status: u8) -> Result<Self, ProgramError> {
+        match status {
+            0 => Ok(EpochStatus::Open),
+            1 => Ok(EpochStatus::Submitted),
+            2 => Ok(EpochStatus::Challenged),
+            3 => Ok(EpochStatus::Finalized),
+            4 => Ok(EpochStatus::Slashed),
+            5 => Ok(EpochStatus::Missed),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Content an object had before an UpdateCommitment, kept while epochs
+/// opened against it are outstanding. Entries with no open epochs are free.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct PreviousVersion {
+    pub version: PodU32,
+    pub commitment: [u8; 32],
+    pub size: PodU64,
+    pub chunk_count: PodU64,
+    pub open_epochs: PodU64, // epochs of this version not yet complete
+}
+
+#[repr(C)]
 pub struct ObjectRecord {
     pub owner: Pubkey,
-    pub commitment: [u8; 32], // commitment/descriptor (CID or hash)
-    pub proof_type: ProofType,
-    pub size: u64,
-    pub created_ts: i64,
-    pub retention_epochs: u64,
+    pub creator: Pubkey, // original owner, fixed; seeds the object PDA
+    pub object_id: PodU128,
+    pub commitment: [u8; 32], // merkle root over CHUNK_SIZE chunks
+    pub proof_type: u8, // ProofType, see proof_type()
+    pub size: PodU64,
+    pub chunk_count: PodU64,
+    pub created_ts: PodI64,
+    pub retention_epochs: PodU64,
+    pub replication_factor: u8,
+    pub assigned_miners: [Pubkey; MAX_REPLICATION], // first replication_factor entries are set; cleared by ReleaseReplica
+    pub epoch_payment: PodU64, // escrow paid per finalised epoch, per replica
+    pub deposit: PodU64,
+    pub epochs_opened: [PodU64; MAX_REPLICATION], // per assigned miner, bounded by retention_epochs
+    pub epochs_completed: [PodU64; MAX_REPLICATION], // finalised, slashed or missed, per assigned miner
+    pub next_epoch_ts: [PodI64; MAX_REPLICATION], // per assigned miner; from then anyone may open its next epoch
+    pub refundable: PodU64, // escrow freed by failed epochs, owed to the owner
+    pub version: PodU32, // bumped by UpdateCommitment
+    pub previous_versions: [PreviousVersion; VERSION_HISTORY], // replaced content still proven by epochs opened before an update
+    pub pending_owner: PodOptionPubkey, // set by a two-step TransferObject until accepted
     pub bump: u8,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+impl ObjectRecord {
+    /// Data offsets for `getProgramAccounts` memcmp filters.
+    pub const OWNER_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(ObjectRecord, owner);
+    pub const CREATOR_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(ObjectRecord, creator);
+
+    pub fn proof_type(&self) -> ProofType {
+        self.proof_type.into()
+    }
+
+    /// Position of `miner` in the assigned set, if it is assigned.
+    pub fn assignment_slot(&self, miner: &Pubkey) -> Option<usize> {
+        self.assigned_miners[..self.replication_factor as usize].iter().position(|m| m == miner)
+    }
+
+    /// Records that an epoch of `miner` reached a terminal status. Returns
+    /// true if it was the last epoch of the replica's retention.
+    pub fn complete_epoch(&mut self, miner: &Pubkey, version: u32) -> bool {
+        let mut served = false;
+        if let Some(slot) = self.assignment_slot(miner) {
+            let completed = &mut self.epochs_completed[slot];
+            completed.set(completed.get().saturating_add(1));
+            served = completed.get() == self.retention_epochs.get();
+        }
+        if let Some(previous) = self.previous_version_mut(version) {
+            previous.open_epochs.set(previous.open_epochs.get().saturating_sub(1));
+        }
+        served
+    }
+
+    /// Epochs opened but not yet complete, across all replicas.
+    pub fn open_epochs(&self) -> u64 {
+        self.epochs_opened
+            .iter()
+            .zip(&self.epochs_completed)
+            .map(|(opened, completed)| opened.get().saturating_sub(completed.get()))
+            .sum()
+    }
+
+    /// Commitment, size and chunk count an epoch of `version` is proven
+    /// against: the current content or, while its epochs are outstanding,
+    /// a version it replaced.
+    pub fn content(&self, version: u32) -> Option<([u8; 32], u64, u64)> {
+        if version == self.version.get() {
+            return Some((self.commitment, self.size.get(), self.chunk_count.get()));
+        }
+        self.previous_versions
+            .iter()
+            .find(|previous| previous.version.get() == version && previous.open_epochs.get() > 0)
+            .map(|previous| (previous.commitment, previous.size.get(), previous.chunk_count.get()))
+    }
+
+    /// The kept entry for a replaced `version`, if it still has open epochs.
+    fn previous_version_mut(&mut self, version: u32) -> Option<&mut PreviousVersion> {
+        if version == self.version.get() {
+            return None;
+        }
+        self.previous_versions
+            .iter_mut()
+            .find(|previous| previous.version.get() == version && previous.open_epochs.get() > 0)
+    }
+
+    /// True if the owner released the replica in `slot` (see ReleaseReplica).
+    pub fn is_released(&self, slot: usize) -> bool {
+        self.assigned_miners[slot] == Pubkey::default()
+    }
+
+    /// Slots of the replicas still held by their assigned miner.
+    pub fn active_slots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.replication_factor as usize).filter(|&slot| !self.is_released(slot))
+    }
+
+    /// True once every replica still held has completed its full retention.
+    pub fn is_expired(&self) -> bool {
+        let retention_epochs = self.retention_epochs.get();
+        self.active_slots().all(|slot| self.epochs_completed[slot].get() >= retention_epochs)
+    }
+}
+
+unsafe impl ZeroCopy for ObjectRecord {
+    const DISCRIMINATOR: [u8; 8] = [213, 130, 79, 121, 145, 232, 23, 36];
+    const VERSION: u8 = 2;
+}
+
+#[repr(C)]
 pub struct EpochRecord {
+    pub object_id: PodU128,
+    pub object: Pubkey, // object account; handlers only accept this one, since ids are per creator
+    pub epoch_id: PodU128,
+    pub nonce: PodU64, // drawn from the seed slot hash by SubmitProof
+    pub seed_slot: PodU64, // first slot whose hash may seed the challenges
+    pub deadline_ts: PodI64,
+    pub challenges: [PodU64; CHALLENGES_PER_EPOCH], // chunk indices derived from nonce
+    pub miner: Pubkey, // assigned miner, slashed if the deadline is missed
+    pub solver: PodOptionPubkey,
+    pub proof_hash: [u8; 32], // response digest over the challenged chunks, folded in as they are proven
+    pub submitted_at: PodI64, // FinalizeEpoch waits dispute_window_secs from here
+    pub status: u8, // EpochStatus, see status()
+    pub proven: u8, // challenges proven so far, in order; the proof is submitted once all are
+    pub reward: PodU64, // emission credited to the solver
+    pub payment: PodU64, // escrow paid to the solver
+    pub version: PodU32, // object content version being challenged
+    pub opener: Pubkey, // paid the rent, which CloseEpoch returns to it
+    pub bump: u8,
+}
+
+impl EpochRecord {
+    /// Data offsets for `getProgramAccounts` memcmp filters.
+    pub const OBJECT_ID_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(EpochRecord, object_id);
+    pub const OBJECT_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(EpochRecord, object);
+    pub const MINER_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(EpochRecord, miner);
+    pub const STATUS_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(EpochRecord, status);
+
+    pub fn status(&self) -> Result<EpochStatus, ProgramError> {
+        self.status.try_into()
+    }
+
+    pub fn set_status(&mut self, status: EpochStatus) {
+        self.status = status as u8;
+    }
+
+    pub fn challenges(&self) -> [u64; CHALLENGES_PER_EPOCH] {
+        self.challenges.map(|index| index.get())
+    }
+
+    /// True once SubmitProof has verified a proof of chunk `index`.
+    pub fn is_proven(&self, index: u64) -> bool {
+        self.challenges[..self.proven as usize].iter().any(|challenge| challenge.get() == index)
+    }
+}
+
+unsafe impl ZeroCopy for EpochRecord {
+    const DISCRIMINATOR: [u8; 8] = [193, 72, 26, 28, 161, 98, 58, 132];
+    const VERSION: u8 = 2;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub enum ChallengeStatus {
+    Pending,
+    Upheld,   // bond returned with a share of the slashed stake
+    Rejected, // bond forfeited
+    Answered, // chunk produced by AnswerChallenge; ResolveChallenge rejects it
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ChallengeRecord {
     pub object_id: u128,
     pub epoch_id: u128,
-    pub nonce: u64,
-    pub deadline_ts: i64,
-    pub solver: Option<Pubkey>,
-    pub proof_hash: [u8; 32], // hash of proof blob
-    pub status: EpochStatus,
-    pub reward: u64,
+    pub challenger: Pubkey,
+    pub chunk_index: u64, // chunk the solver must produce to answer the challenge
+    pub bond: u64,
+    pub created_at: i64,
+    pub status: ChallengeStatus,
     pub bump: u8,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+impl ProgramAccount for ChallengeRecord {
+    const DISCRIMINATOR: [u8; 8] = [226, 106, 205, 134, 142, 29, 8, 81];
+    const VERSION: u8 = 1;
+    // object_id, epoch_id, challenger, chunk_index, bond, created_at, status, bump
+    const LEN: usize = 16 + 16 + 32 + 8 + 8 + 8 + 1 + 1;
+}
+
+#[repr(C)]
 pub struct MinerAccount {
     pub miner: Pubkey,
-    pub stake: u64,
-    pub pending_rewards: u64,
-    pub reputation: u32,
-    pub unstake_ts: i64,
+    pub operator: Pubkey, // hot key allowed to submit proofs for the miner
+    pub stake: PodU64,
+    pub pending_rewards: PodU64,
+    pub reputation: PodU32,
+    pub unstake_ts: PodI64,
+    pub metadata: [u8; MINER_METADATA_LEN], // e.g. retrieval endpoint
+    pub open_epochs: PodU64, // opened but not yet finalised, slashed or missed
+    pub assignments: PodU64, // replicas held that have neither served their retention nor been released
     pub bump: u8,
 }
 
+impl MinerAccount {
+    /// Data offsets for `getProgramAccounts` memcmp filters.
+    pub const MINER_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(MinerAccount, miner);
+    pub const OPERATOR_OFFSET: usize = ACCOUNT_HEADER_LEN + offset_of!(MinerAccount, operator);
+
+    /// Records that one of the miner's epochs reached a terminal status,
+    /// and whether it ended the replica's retention.
+    pub fn complete_epoch(&mut self, served: bool) {
+        self.open_epochs.set(self.open_epochs.get().saturating_sub(1));
+        if served {
+            self.end_assignment();
+        }
+    }
+
+    pub fn add_assignment(&mut self) {
+        self.assignments.set(self.assignments.get().saturating_add(1));
+    }
+
+    pub fn end_assignment(&mut self) {
+        self.assignments.set(self.assignments.get().saturating_sub(1));
+    }
+}
+
+unsafe impl ZeroCopy for MinerAccount {
+    const DISCRIMINATOR: [u8; 8] = [232, 196, 79, 139, 222, 213, 161, 99];
+    const VERSION: u8 = 2;
+}
+
+/// Every registered miner. RegisterObject draws assignees from the whole set
+/// rather than from miners the owner picks.
+#[repr(C)]
+pub struct MinerRegistry {
+    pub miners: [Pubkey; MAX_MINERS], // first count entries are set
+    pub count: u8,
+    pub draws: PodU64, // assignment draws so far; seeds the next draw
+    pub bump: u8,
+}
+
+impl MinerRegistry {
+    pub fn miners(&self) -> &[Pubkey] {
+        &self.miners[..self.count as usize]
+    }
+
+    /// Adds `miner` unless it is already registered.
+    pub fn insert(&mut self, miner: &Pubkey) -> ProgramResult {
+        if self.miners().contains(miner) {
+            return Ok(());
+        }
+        let count = self.count as usize;
+        if count == MAX_MINERS {
+            msg!("Miner registry full");
+            return Err(ContractError::RegistryFull.into());
+        }
+        self.miners[count] = *miner;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Removes `miner` if registered, moving the last entry into its place.
+    pub fn remove(&mut self, miner: &Pubkey) {
+        if let Some(pos) = self.miners().iter().position(|m| m == miner) {
+            let last = self.count as usize - 1;
+            self.miners[pos] = self.miners[last];
+            self.miners[last] = Pubkey::default();
+            self.count -= 1;
+        }
+    }
+}
+
+unsafe impl ZeroCopy for MinerRegistry {
+    const DISCRIMINATOR: [u8; 8] = [153, 124, 48, 179, 142, 207, 88, 168];
+    const VERSION: u8 = 1;
+}
+
+// The zero-copy layouts may sit at any offset after the header.
+const _: () = assert!(align_of::<GlobalState>() == 1);
+const _: () = assert!(align_of::<ObjectRecord>() == 1);
+const _: () = assert!(align_of::<EpochRecord>() == 1);
+const _: () = assert!(align_of::<MinerAccount>() == 1);
+const _: () = assert!(align_of::<MinerRegistry>() == 1);
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ChunkProof {
+    pub chunk: Vec<u8>,
+    pub path: Vec<[u8; 32]>, // sibling hashes from leaf to root
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum RewardInstruction {
     Initialize {
         decay_numerator: u64,
         decay_denom: u64,
         emission_cap: u64,
+        emission_mode: u8,
     },
     RegisterObject {
         commitment: [u8; 32],
         proof_type: u8,
         size: u64,
+        chunk_count: u64,
         retention_epochs: u64,
         object_id: u128,
+        replication_factor: u8,
     },
     CreateEpoch { 
-        object_id: u128, 
-        nonce: u64, 
-        epoch_id: u128 
+        object_id: u128, // the epoch id is the replica's next one, see create_epoch
     },
     SubmitProof { 
         epoch_id: u128, 
-        proof_hash: [u8; 32] 
+        proof_hash: [u8; 32],
+        proofs: Vec<ChunkProof>, // the next challenged chunks for CompactHash objects; proof_hash is checked once all are proven
     },
     ChallengeProof { 
         epoch_id: u128, 
-        evidence_hash: [u8; 32] 
+        chunk_index: u64, // a chunk the submitted proof did not cover
     },
     FinalizeEpoch { 
         epoch_id: u128 
@@ -102,4 +693,117 @@ pub enum RewardInstruction {
         miner: Pubkey, 
         amount: u64 
     },
+    RegisterMiner {
+        operator: Pubkey,
+        metadata: [u8; MINER_METADATA_LEN],
+    },
+    DeregisterMiner,
+    ClaimRewards,
+    ResolveChallenge {
+        epoch_id: u128,
+    },
+    MarkMissed {
+        epoch_id: u128,
+    },
+    RefundEscrow {
+        object_id: u128,
+    },
+    CloseObject {
+        object_id: u128,
+    },
+    CloseEpoch {
+        epoch_id: u128,
+    },
+    ExtendRetention {
+        object_id: u128,
+        additional_epochs: u64,
+    },
+    UpdateCommitment {
+        object_id: u128,
+        commitment: [u8; 32],
+        size: u64,
+        chunk_count: u64,
+    },
+    TransferObject {
+        object_id: u128,
+        new_owner: Pubkey,
+        require_accept: bool,
+    },
+    AcceptObject {
+        object_id: u128,
+    },
+    ReleaseReplica {
+        object_id: u128,
+        miner: Pubkey,
+    },
+    ProposeAdmin {
+        new_admin: Pubkey,
+    },
+    AcceptAdmin,
+    SetPause {
+        paused: u64,
+    },
+    SetAdmins {
+        admins: Vec<Pubkey>,
+        threshold: u8,
+    },
+    UpdateConfig {
+        params: ConfigParams,
+    },
+    Migrate {
+        kind: AccountKind,
+    },
+    MigrateStake {
+        miner: Pubkey,
+    },
+    AnswerChallenge {
+        epoch_id: u128,
+        proof: ChunkProof, // the challenged chunk
+    },
+}
+
+/// Account type a Migrate targets. Accounts the first deployment wrote carry
+/// no tag, so the caller names the type, and for objects the id their record
+/// did not keep.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum AccountKind {
+    Global,
+    Object { object_id: u128 },
+    Epoch,
+    Miner,
+}
+
+impl RewardInstruction {
+    /// Bit in `GlobalState.paused` that halts this instruction. Initialize,
+    /// the governance instructions and the migrations (Migrate must be able
+    /// to upgrade the global account itself) cannot be paused.
+    pub fn pause_bit(&self) -> Option<u32> {
+        use RewardInstruction::*;
+        let bit = match self {
+            Initialize { .. } | ProposeAdmin { .. } | AcceptAdmin | SetPause { .. } | SetAdmins { .. } | UpdateConfig { .. } | Migrate { .. } | MigrateStake { .. } => return None,
+            RegisterObject { .. } => 1,
+            CreateEpoch { .. } => 2,
+            SubmitProof { .. } => 3,
+            ChallengeProof { .. } => 4,
+            FinalizeEpoch { .. } => 5,
+            Stake { .. } => 6,
+            Unstake { .. } => 7,
+            Slash { .. } => 8,
+            RegisterMiner { .. } => 9,
+            DeregisterMiner => 10,
+            ClaimRewards => 11,
+            ResolveChallenge { .. } => 12,
+            MarkMissed { .. } => 13,
+            RefundEscrow { .. } => 14,
+            CloseObject { .. } => 15,
+            CloseEpoch { .. } => 16,
+            ExtendRetention { .. } => 17,
+            UpdateCommitment { .. } => 18,
+            TransferObject { .. } => 19,
+            AcceptObject { .. } => 20,
+            ReleaseReplica { .. } => 21,
+            AnswerChallenge { .. } => 22,
+        };
+        Some(bit)
+    }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
+use contract::ConfigParams;
 use solana_program::instruction::Instruction;
 use solana_sdk::signature::{Keypair, Signer};
 use solana_sdk::transaction::Transaction;
@@ -15,22 +16,28 @@ pub enum TapeInstruction {
         decay_n: u64,
         decay_d: u64,
         emission_cap: u64,
+        emission_mode: u8,
     },
     RegisterObject {
         commitment: [u8; 32],
         proof_type: u8,
         size: u64,
+        chunk_count: u64,
         retention_epochs: u64,
         object_id: u128,
+        replication_factor: u8,
     },
     CreateEpoch {
         object_id: u128,
-        nonce: u64,
-        epoch_id: u128,
     },
     SubmitProof {
         epoch_id: u128,
         proof_hash: [u8; 32],
+        proofs: Vec<ChunkProof>,
+    },
+    ChallengeProof {
+        epoch_id: u128,
+        chunk_index: u64,
     },
     FinalizeEpoch {
         epoch_id: u128,
@@ -41,7 +48,109 @@ pub enum TapeInstruction {
     Unstake {
         amount: u64,
     },
-    Claim {},
+    Slash {
+        miner: Pubkey,
+        amount: u64,
+    },
+    RegisterMiner {
+        operator: Pubkey,
+        metadata: [u8; 64],
+    },
+    DeregisterMiner,
+    ClaimRewards,
+    ResolveChallenge {
+        epoch_id: u128,
+    },
+    MarkMissed {
+        epoch_id: u128,
+    },
+    RefundEscrow {
+        object_id: u128,
+    },
+    CloseObject {
+        object_id: u128,
+    },
+    CloseEpoch {
+        epoch_id: u128,
+    },
+    ExtendRetention {
+        object_id: u128,
+        additional_epochs: u64,
+    },
+    UpdateCommitment {
+        object_id: u128,
+        commitment: [u8; 32],
+        size: u64,
+        chunk_count: u64,
+    },
+    TransferObject {
+        object_id: u128,
+        new_owner: Pubkey,
+        require_accept: bool,
+    },
+    AcceptObject {
+        object_id: u128,
+    },
+    ReleaseReplica {
+        object_id: u128,
+        miner: Pubkey,
+    },
+    ProposeAdmin {
+        new_admin: Pubkey,
+    },
+    AcceptAdmin,
+    SetPause {
+        paused: u64,
+    },
+    SetAdmins {
+        admins: Vec<Pubkey>,
+        threshold: u8,
+    },
+    UpdateConfig {
+        params: ConfigParams,
+    },
+    Migrate {
+        kind: AccountKind,
+    },
+    MigrateStake {
+        miner: Pubkey,
+    },
+    AnswerChallenge {
+        epoch_id: u128,
+        proof: ChunkProof,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub enum AccountKind {
+    Global,
+    Object { object_id: u128 },
+    Epoch,
+    Miner,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ChunkProof {
+    pub chunk: Vec<u8>,
+    pub path: Vec<[u8; 32]>,
+}
+
+/// `Option<Pubkey>` as the program lays it out: a flag byte followed by the
+/// key, 33 bytes either way.
+mod fixed_option {
+    use borsh::io::{Read, Result, Write};
+    use super::Pubkey;
+
+    pub fn serialize<W: Write>(value: &Option<Pubkey>, writer: &mut W) -> Result<()> {
+        writer.write_all(&[value.is_some() as u8])?;
+        writer.write_all(value.unwrap_or_default().as_ref())
+    }
+
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Option<Pubkey>> {
+        let mut bytes = [0u8; 33];
+        reader.read_exact(&mut bytes)?;
+        Ok((bytes[0] != 0).then(|| Pubkey::new_from_array(bytes[1..].try_into().unwrap())))
+    }
 }
 
 // Define account structures matching your program
@@ -50,22 +159,60 @@ pub struct GlobalState {
     pub admin: Pubkey,
     pub tape_mint: Pubkey,
     pub reward_vault: Pubkey,
+    pub emission_mode: u8, // 0 = Vault, 1 = Mint
+    pub total_minted: u64,
+    pub total_allocated: u64,
+    pub emission_cap: u64,
     pub decay_n: u64,
     pub decay_d: u64,
-    pub emission_cap: u64,
-    pub total_staked: u64,
+    pub last_decay_at: i64,
+    pub epoch_reward: u64,
+    #[borsh(serialize_with = "fixed_option::serialize", deserialize_with = "fixed_option::deserialize")]
+    pub pending_admin: Option<Pubkey>,
+    pub paused: u64,
+    pub admins: [Pubkey; 5],
+    pub admin_count: u8,
+    pub admin_threshold: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Config {
+    pub params: ConfigParams,
     pub bump: u8,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, PartialEq)]
+pub struct PreviousVersion {
+    pub version: u32,
+    pub commitment: [u8; 32],
+    pub size: u64,
+    pub chunk_count: u64,
+    pub open_epochs: u64,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ObjectRecord {
     pub owner: Pubkey,
+    pub creator: Pubkey,
+    pub object_id: u128,
     pub commitment: [u8; 32],
     pub proof_type: u8,
     pub size: u64,
+    pub chunk_count: u64,
+    pub created_ts: i64,
     pub retention_epochs: u64,
-    pub object_id: u128,
-    pub created_at: i64,
+    pub replication_factor: u8,
+    pub assigned_miners: [Pubkey; 4],
+    pub epoch_payment: u64,
+    pub deposit: u64,
+    pub epochs_opened: [u64; 4],
+    pub epochs_completed: [u64; 4],
+    pub next_epoch_ts: [i64; 4],
+    pub refundable: u64,
+    pub version: u32,
+    pub previous_versions: [PreviousVersion; 2],
+    #[borsh(serialize_with = "fixed_option::serialize", deserialize_with = "fixed_option::deserialize")]
+    pub pending_owner: Option<Pubkey>,
     pub bump: u8,
 }
 
@@ -73,38 +220,233 @@ pub struct ObjectRecord {
 pub enum EpochStatus {
     Open,
     Submitted,
+    Challenged,
     Finalized,
+    Slashed,
+    Missed,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct EpochRecord {
     pub object_id: u128,
+    pub object: Pubkey,
     pub epoch_id: u128,
     pub nonce: u64,
-    pub status: EpochStatus,
+    pub seed_slot: u64,
+    pub deadline_ts: i64,
+    pub challenges: [u64; 2],
+    pub miner: Pubkey,
+    #[borsh(serialize_with = "fixed_option::serialize", deserialize_with = "fixed_option::deserialize")]
     pub solver: Option<Pubkey>,
-    pub proof_hash: Option<[u8; 32]>,
+    pub proof_hash: [u8; 32],
+    pub submitted_at: i64,
+    pub status: EpochStatus,
+    pub proven: u8,
     pub reward: u64,
+    pub payment: u64,
+    pub version: u32,
+    pub opener: Pubkey,
+    pub bump: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub enum ChallengeStatus {
+    Pending,
+    Upheld,
+    Rejected,
+    Answered,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ChallengeRecord {
+    pub object_id: u128,
+    pub epoch_id: u128,
+    pub challenger: Pubkey,
+    pub chunk_index: u64,
+    pub bond: u64,
     pub created_at: i64,
+    pub status: ChallengeStatus,
     pub bump: u8,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct MinerAccount {
+    pub miner: Pubkey,
+    pub operator: Pubkey,
+    pub stake: u64,
+    pub pending_rewards: u64,
+    pub reputation: u32,
+    pub unstake_ts: i64,
+    pub metadata: [u8; 64],
+    pub open_epochs: u64,
+    pub assignments: u64,
+    pub bump: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct MinerRegistry {
+    pub miners: [Pubkey; 32],
+    pub count: u8,
+    pub draws: u64,
+    pub bump: u8,
+}
+
+/// Layouts the first deployment wrote: header-less Borsh bodies. A proof
+/// type of 0 encodes the same as its CompactHash variant.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct GlobalStateV0 {
+    pub admin: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_vault: Pubkey,
+    pub total_minted: u64,
+    pub emission_cap: u64,
+    pub decay_numerator: u64,
+    pub decay_denom: u64,
+    pub last_decay_at: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ObjectRecordV0 {
+    pub owner: Pubkey,
+    pub commitment: [u8; 32],
+    pub proof_type: u8,
+    pub size: u64,
+    pub created_ts: i64,
+    pub retention_epochs: u64,
+    pub bump: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct EpochRecordV0 {
+    pub object_id: u128,
+    pub epoch_id: u128,
+    pub nonce: u64,
+    pub deadline_ts: i64,
+    pub solver: Option<Pubkey>,
+    pub proof_hash: [u8; 32],
+    pub status: EpochStatus,
+    pub reward: u64,
+    pub bump: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct MinerAccountV0 {
     pub miner: Pubkey,
     pub stake: u64,
     pub pending_rewards: u64,
-    pub reputation: u64,
+    pub reputation: u32,
     pub unstake_ts: i64,
     pub bump: u8,
 }
 
+/// Discriminator the program writes ahead of each account, followed by the
+/// layout version. Version 2 accounts are the fixed zero-copy layouts, which
+/// the mirrors above encode byte for byte.
+pub trait Tagged: BorshSerialize + BorshDeserialize {
+    const DISCRIMINATOR: [u8; 8];
+    const VERSION: u8;
+}
+
+impl Tagged for GlobalState {
+    const DISCRIMINATOR: [u8; 8] = [163, 46, 74, 168, 216, 123, 133, 98];
+    const VERSION: u8 = 2;
+}
+
+impl Tagged for Config {
+    const DISCRIMINATOR: [u8; 8] = [155, 12, 170, 224, 30, 250, 204, 130];
+    const VERSION: u8 = 1;
+}
+
+impl Tagged for ObjectRecord {
+    const DISCRIMINATOR: [u8; 8] = [213, 130, 79, 121, 145, 232, 23, 36];
+    const VERSION: u8 = 2;
+}
+
+impl Tagged for EpochRecord {
+    const DISCRIMINATOR: [u8; 8] = [193, 72, 26, 28, 161, 98, 58, 132];
+    const VERSION: u8 = 2;
+}
+
+impl Tagged for ChallengeRecord {
+    const DISCRIMINATOR: [u8; 8] = [226, 106, 205, 134, 142, 29, 8, 81];
+    const VERSION: u8 = 1;
+}
+
+impl Tagged for MinerAccount {
+    const DISCRIMINATOR: [u8; 8] = [232, 196, 79, 139, 222, 213, 161, 99];
+    const VERSION: u8 = 2;
+}
+
+impl Tagged for MinerRegistry {
+    const DISCRIMINATOR: [u8; 8] = [153, 124, 48, 179, 142, 207, 88, 168];
+    const VERSION: u8 = 1;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use litesvm::LiteSVM;
+    use solana_program::hash::{hashv, Hash};
+    use solana_program::clock::Clock;
+    use solana_program::slot_hashes::SlotHashes;
+    use solana_sdk::account::Account;
+    use solana_program::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use contract::events::ContractEvent;
+    use spl_token::solana_program::program_option::COption;
     use spl_token::solana_program::program_pack::Pack;
 
+    /// Helper: admin set with `keys` in the first slots
+    fn admin_set(keys: &[Pubkey]) -> [Pubkey; 5] {
+        let mut admins = [Pubkey::default(); 5];
+        admins[..keys.len()].copy_from_slice(keys);
+        admins
+    }
+
+    /// Helper: derive PDA for the protocol config
+    fn derive_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"config"], program_id)
+    }
+
+    /// Helper: write the default config, as Initialize would
+    fn seed_config(svm: &mut LiteSVM, program_id: &Pubkey) -> Pubkey {
+        let (config_pda, bump) = derive_config_pda(program_id);
+        let config = Config { params: ConfigParams::default(), bump };
+        set_account_data(svm, config_pda, *program_id, account_bytes(&config));
+        config_pda
+    }
+
+    /// Helper: global state as Initialize leaves it, with `admin` as the sole
+    /// admin; tests override the fields they need with struct update syntax
+    fn default_global(admin: Pubkey, mint: Pubkey) -> GlobalState {
+        GlobalState {
+            admin,
+            tape_mint: mint,
+            reward_vault: Pubkey::new_unique(),
+            emission_mode: 0,
+            total_minted: 0,
+            total_allocated: 0,
+            emission_cap: 7_000_000,
+            decay_n: 15,
+            decay_d: 100,
+            last_decay_at: 0,
+            epoch_reward: 1_000_000,
+            pending_admin: None,
+            paused: 0,
+            admins: admin_set(&[admin]),
+            admin_count: 1,
+            admin_threshold: 1,
+        }
+    }
+
+    /// Helper: write the global state at its PDA
+    fn seed_global(svm: &mut LiteSVM, program_id: &Pubkey, global_state: &GlobalState) -> Pubkey {
+        let (global_pda, _) = derive_global_pda(program_id);
+        set_account_data(svm, global_pda, *program_id, account_bytes(global_state));
+        global_pda
+    }
+
     /// Helper: derive PDA for global state
     fn derive_global_pda(program_id: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(&[b"global"], program_id)
@@ -115,16 +457,168 @@ mod tests {
         Pubkey::find_program_address(&[b"object", owner.as_ref(), &idb], program_id)
     }
 
-    fn derive_epoch_pda(program_id: &Pubkey, object_id: u128, epoch_id: u128) -> (Pubkey, u8) {
-        let oid = object_id.to_le_bytes();
+    fn derive_epoch_pda(program_id: &Pubkey, object: &Pubkey, miner: &Pubkey, epoch_id: u128) -> (Pubkey, u8) {
         let eid = epoch_id.to_le_bytes();
-        Pubkey::find_program_address(&[b"epoch", &oid, &eid], program_id)
+        Pubkey::find_program_address(&[b"epoch", object.as_ref(), miner.as_ref(), &eid], program_id)
+    }
+
+    fn assigned(miner: Pubkey) -> [Pubkey; 4] {
+        [miner, Pubkey::default(), Pubkey::default(), Pubkey::default()]
+    }
+
+    fn derive_challenge_pda(program_id: &Pubkey, object: &Pubkey, miner: &Pubkey, epoch_id: u128) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"challenge", object.as_ref(), miner.as_ref(), &epoch_id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    fn derive_escrow_pda(program_id: &Pubkey, object: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"escrow", object.as_ref()], program_id)
+    }
+
+    fn derive_treasury_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"treasury"], program_id)
     }
 
     fn derive_miner_pda(program_id: &Pubkey, miner: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(&[b"miner", miner.as_ref()], program_id)
     }
 
+    fn derive_stake_vault_pda(program_id: &Pubkey, miner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"stake_vault", miner.as_ref()], program_id)
+    }
+
+    fn derive_registry_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"registry"], program_id)
+    }
+
+    fn derive_bond_vault_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"bond_vault"], program_id)
+    }
+
+    const TOKEN_PROGRAM_ID: Pubkey = Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+    /// Helper: load the program built by `cargo build-sbf`
+    fn load_program(svm: &mut LiteSVM) -> Pubkey {
+        let program_id = Pubkey::new_unique();
+        svm.add_program_from_file(
+            program_id,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/target/deploy/contract.so"),
+        )
+        .unwrap();
+        program_id
+    }
+
+    /// Helper: write a rent-exempt account directly into the SVM
+    fn set_account_data(svm: &mut LiteSVM, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let lamports = svm.minimum_balance_for_rent_exemption(data.len());
+        svm.set_account(key, Account { lamports, data, owner, executable: false, rent_epoch: 0 }).unwrap();
+    }
+
+    /// Helper: write a miner account and append it to the registry
+    fn seed_miner(svm: &mut LiteSVM, program_id: &Pubkey, miner: &Pubkey, stake: u64) -> Pubkey {
+        let (miner_pda, miner_bump) = derive_miner_pda(program_id, miner);
+        let miner_acc = MinerAccount {
+            miner: *miner,
+            operator: *miner,
+            stake,
+            pending_rewards: 0,
+            reputation: 100,
+            unstake_ts: 0,
+            metadata: [0u8; 64],
+            open_epochs: 0,
+            assignments: 0,
+            bump: miner_bump,
+        };
+        set_account_data(svm, miner_pda, *program_id, account_bytes(&miner_acc));
+
+        let (registry_pda, registry_bump) = derive_registry_pda(program_id);
+        let mut registry = match svm.get_account(&registry_pda) {
+            Some(account) if !account.data.is_empty() => decode_account(&account.data),
+            _ => MinerRegistry { miners: [Pubkey::default(); 32], count: 0, draws: 0, bump: registry_bump },
+        };
+        registry.miners[registry.count as usize] = *miner;
+        registry.count += 1;
+        set_account_data(svm, registry_pda, *program_id, account_bytes(&registry));
+        miner_pda
+    }
+
+    fn set_token_account(svm: &mut LiteSVM, key: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let mut data = vec![0u8; 165];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[108] = 1; // AccountState::Initialized
+        set_account_data(svm, key, TOKEN_PROGRAM_ID, data);
+    }
+
+    fn set_mint(svm: &mut LiteSVM, key: Pubkey, authority: &Pubkey) {
+        let mut data = vec![0u8; Mint::LEN];
+        Mint {
+            mint_authority: COption::Some(*authority),
+            supply: 0,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }.pack_into_slice(&mut data);
+        set_account_data(svm, key, TOKEN_PROGRAM_ID, data);
+    }
+
+    fn token_balance(svm: &LiteSVM, key: &Pubkey) -> u64 {
+        let data = svm.get_account(key).expect("token account").data;
+        u64::from_le_bytes(data[64..72].try_into().unwrap())
+    }
+
+    fn custom_error(result: litesvm::types::TransactionResult) -> Option<u32> {
+        match result.err()?.err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+            _ => None,
+        }
+    }
+
+    fn logged_events(result: &litesvm::types::TransactionResult) -> Vec<ContractEvent> {
+        let logs = match result {
+            Ok(meta) => &meta.logs,
+            Err(failed) => &failed.meta.logs,
+        };
+        logs.iter()
+            .filter_map(|line| line.strip_prefix("Program data: "))
+            .filter_map(|data| STANDARD.decode(data).ok())
+            .filter_map(|bytes| ContractEvent::decode(&bytes))
+            .collect()
+    }
+
+    /// Helper: program account bytes with the current header
+    fn account_bytes<T: Tagged>(value: &T) -> Vec<u8> {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.push(T::VERSION);
+        data.extend(borsh::to_vec(value).unwrap());
+        data
+    }
+
+    fn decode_account<T: Tagged>(data: &[u8]) -> T {
+        assert_eq!(data[..8], T::DISCRIMINATOR, "account discriminator");
+        assert_eq!(data[8], T::VERSION, "account version");
+        T::deserialize(&mut &data[9..]).expect("deserialize account")
+    }
+
+    fn read_account<T: Tagged>(svm: &LiteSVM, key: &Pubkey) -> T {
+        let account = svm.get_account(key).expect("account not found");
+        decode_account(&account.data)
+    }
+
+    fn send(svm: &mut LiteSVM, ix: Instruction, signers: &[&Keypair]) -> litesvm::types::TransactionResult {
+        svm.expire_blockhash();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&signers[0].pubkey()),
+            signers,
+            svm.latest_blockhash(),
+        );
+        svm.send_transaction(tx)
+    }
+
     #[test]
     fn test_integration_flow_all() {
         // Initialize LiteSVM with your program
@@ -142,6 +636,7 @@ mod tests {
 
         // ---- Initialize: create GlobalState PDA, mint account, reward vault ----
         let (global_pda, _global_bump) = derive_global_pda(&program_id);
+        let (config_pda, _) = derive_config_pda(&program_id);
         
         // Create global PDA account
         let global_space = 512usize;
@@ -206,7 +701,8 @@ mod tests {
             &TapeInstruction::Initialize { 
                 decay_n: 15, 
                 decay_d: 100, 
-                emission_cap: 7_000_000 
+                emission_cap: 7_000_000,
+                emission_mode: 0,
             },
             vec![
                 solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
@@ -216,6 +712,8 @@ mod tests {
                 solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
                 solana_program::instruction::AccountMeta::new_readonly(spl_token::id(), false),
                 solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(derive_treasury_pda(&program_id).0, false),
+                solana_program::instruction::AccountMeta::new(config_pda, false),
             ],
         );
 
@@ -229,36 +727,105 @@ mod tests {
 
         // Read back GlobalState
         let global_account = svm.get_account(&global_pda).expect("global not found");
-        let global_state = GlobalState::try_from_slice(&global_account.data).expect("deserialize global");
+        let global_state = decode_account::<GlobalState>(&global_account.data);
         assert_eq!(global_state.admin, payer.pubkey());
         assert_eq!(global_state.tape_mint, tape_mint.pubkey());
 
-        // ---- Register object ----
-        let user = Keypair::new();
-        svm.airdrop(&user.pubkey(), 1_000_000_000).unwrap();
+        // ---- Register & stake miner ----
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 500_000_000).unwrap();
 
-        let object_id: u128 = 42;
-        let (object_pda, _obj_bump) = derive_object_pda(&program_id, &user.pubkey(), object_id);
-        
-        // Create object PDA account
-        let object_space = 1024usize;
-        let obj_rent = svm.minimum_balance_for_rent_exemption(object_space);
-        let create_object_ix = create_account(
-            &payer.pubkey(), 
-            &object_pda, 
-            obj_rent, 
-            object_space as u64, 
-            &program_id
+        let (miner_pda, _mbump) = derive_miner_pda(&program_id, &miner.pubkey());
+
+        // Register miner
+        let register_miner_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::RegisterMiner {
+                operator: miner.pubkey(),
+                metadata: [0u8; 64],
+            },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            ],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[register_miner_ix],
+            Some(&payer.pubkey()),
+            &[&payer, &miner],
+            svm.latest_blockhash(),
+        );
+        svm.send_transaction(tx).unwrap();
+
+        // Create miner ATA
+        let miner_pub = miner.pubkey();
+        let miner_ata = get_associated_token_address(&miner_pub, &tape_mint_pub);
+        let create_miner_ata_ix = spl_associated_token_account::instruction::create_associated_token_account(
+            &payer.pubkey(),
+            &miner_pub,
+            &tape_mint_pub,
+            &spl_token::id(),
         );
         
         let tx = Transaction::new_signed_with_payer(
-            &[create_object_ix],
+            &[create_miner_ata_ix],
             Some(&payer.pubkey()),
             &[&payer],
             svm.latest_blockhash(),
         );
         svm.send_transaction(tx).unwrap();
 
+        // Call stake instruction
+        let stake_amount = 1_000u64;
+        set_token_account(&mut svm, miner_ata, &tape_mint_pub, &miner_pub, stake_amount);
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner_pub);
+        let stake_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Stake { amount: stake_amount },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_ata, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(spl_token::id(), false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(tape_mint_pub, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+            ],
+        );
+        
+        let tx = Transaction::new_signed_with_payer(
+            &[stake_ix],
+            Some(&payer.pubkey()),
+            &[&payer, &miner],
+            svm.latest_blockhash(),
+        );
+        svm.send_transaction(tx).unwrap();
+
+        // The first stake creates the miner's vault, held by the global PDA.
+        let vault = svm.get_account(&stake_vault).expect("stake vault not created");
+        assert_eq!(vault.owner, spl_token::id());
+        let vault_state = spl_token::state::Account::unpack(&vault.data).unwrap();
+        assert_eq!(vault_state.owner, global_pda);
+        assert_eq!(vault_state.mint, tape_mint_pub);
+        assert_eq!(vault_state.amount, stake_amount);
+
+        // ---- Register object ----
+        let user = Keypair::new();
+        svm.airdrop(&user.pubkey(), 1_000_000_000).unwrap();
+
+        let object_id: u128 = 42;
+        let (object_pda, _obj_bump) = derive_object_pda(&program_id, &user.pubkey(), object_id);
+
+        // The owner pays 20 chunks * 10 epochs * 1 replica into the escrow
+        let user_ata = Pubkey::new_unique();
+        set_token_account(&mut svm, user_ata, &tape_mint_pub, &user.pubkey(), 2_000);
+
         // Register object
         let commitment = [7u8; 32];
         let register_ix = Instruction::new_with_borsh(
@@ -267,13 +834,24 @@ mod tests {
                 commitment,
                 proof_type: 0u8,
                 size: 1234u64,
+                chunk_count: 20u64,
                 retention_epochs: 10u64,
                 object_id,
+                replication_factor: 1,
             },
             vec![
                 solana_program::instruction::AccountMeta::new(user.pubkey(), true),
                 solana_program::instruction::AccountMeta::new(object_pda, false),
                 solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+                solana_program::instruction::AccountMeta::new(user_ata, false),
+                solana_program::instruction::AccountMeta::new(derive_escrow_pda(&program_id, &object_pda).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(tape_mint_pub, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(spl_token::id(), false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
             ],
         );
         
@@ -287,133 +865,56 @@ mod tests {
 
         // Read back object
         let obj_acct = svm.get_account(&object_pda).expect("object not found");
-        let obj_rec = ObjectRecord::try_from_slice(&obj_acct.data).expect("deserialize object");
+        let obj_rec = decode_account::<ObjectRecord>(&obj_acct.data);
         assert_eq!(obj_rec.owner, user.pubkey());
         assert_eq!(obj_rec.commitment[0], 7u8);
+        assert_eq!(obj_rec.replication_factor, 1);
+        assert_eq!(obj_rec.assigned_miners[0], miner.pubkey());
 
         // ---- Create Epoch ----
-        let epoch_id: u128 = 1001;
-        let nonce: u64 = 9;
-        let (epoch_pda, _bump) = derive_epoch_pda(&program_id, object_id, epoch_id);
-        
-        // Create epoch PDA
-        let epoch_space = 1024usize;
-        let epoch_rent = svm.minimum_balance_for_rent_exemption(epoch_space);
-        let create_epoch_account_ix = create_account(
-            &payer.pubkey(), 
-            &epoch_pda, 
-            epoch_rent, 
-            epoch_space as u64, 
-            &program_id
-        );
-        
-        let tx = Transaction::new_signed_with_payer(
-            &[create_epoch_account_ix],
-            Some(&payer.pubkey()),
-            &[&payer],
-            svm.latest_blockhash(),
-        );
-        svm.send_transaction(tx).unwrap();
+        let epoch_id: u128 = 1;
+        let (epoch_pda, _bump) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
 
         let create_epoch_ix = Instruction::new_with_borsh(
             program_id,
-            &TapeInstruction::CreateEpoch { object_id, nonce, epoch_id },
+            &TapeInstruction::CreateEpoch { object_id },
             vec![
-                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
                 solana_program::instruction::AccountMeta::new(object_pda, false),
                 solana_program::instruction::AccountMeta::new(epoch_pda, false),
                 solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
             ],
         );
         
         let tx = Transaction::new_signed_with_payer(
             &[create_epoch_ix],
             Some(&payer.pubkey()),
-            &[&payer],
+            &[&payer, &miner],
             svm.latest_blockhash(),
         );
         svm.send_transaction(tx).unwrap();
 
         let epoch_acct = svm.get_account(&epoch_pda).expect("epoch not found");
-        let epoch_rec = EpochRecord::try_from_slice(&epoch_acct.data).expect("deserialize epoch");
+        let epoch_rec = decode_account::<EpochRecord>(&epoch_acct.data);
         assert_eq!(epoch_rec.object_id, object_id);
         assert_eq!(epoch_rec.epoch_id, epoch_id);
+        assert_eq!(epoch_rec.miner, miner.pubkey());
         assert_eq!(epoch_rec.status, EpochStatus::Open);
 
-        // ---- Miner stake & submit proof ----
-        let miner = Keypair::new();
-        svm.airdrop(&miner.pubkey(), 500_000_000).unwrap();
-
-        // Create miner PDA
-        let (miner_pda, _mbump) = derive_miner_pda(&program_id, &miner.pubkey());
-        let miner_space = 1024usize;
-        let miner_rent = svm.minimum_balance_for_rent_exemption(miner_space);
-        let create_miner_acc_ix = create_account(
-            &payer.pubkey(), 
-            &miner_pda, 
-            miner_rent, 
-            miner_space as u64, 
-            &program_id
-        );
-        
-        let tx = Transaction::new_signed_with_payer(
-            &[create_miner_acc_ix],
-            Some(&payer.pubkey()),
-            &[&payer],
-            svm.latest_blockhash(),
-        );
-        svm.send_transaction(tx).unwrap();
-        
-        // Create miner ATA
-        let miner_pub = miner.pubkey();
-        let miner_ata = get_associated_token_address(&miner_pub, &tape_mint_pub);
-        let create_miner_ata_ix = spl_associated_token_account::instruction::create_associated_token_account(
-            &payer.pubkey(),
-            &miner_pub,
-            &tape_mint_pub,
-            &spl_token::id(),
-        );
-        
-        let tx = Transaction::new_signed_with_payer(
-            &[create_miner_ata_ix],
-            Some(&payer.pubkey()),
-            &[&payer],
-            svm.latest_blockhash(),
-        );
-        svm.send_transaction(tx).unwrap();
-
-        // Call stake instruction
-        let stake_amount = 500u64;
-        let stake_ix = Instruction::new_with_borsh(
-            program_id,
-            &TapeInstruction::Stake { amount: stake_amount },
-            vec![
-                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
-                solana_program::instruction::AccountMeta::new(miner_ata, false),
-                solana_program::instruction::AccountMeta::new(reward_vault_ata, false),
-                solana_program::instruction::AccountMeta::new(miner_pda, false),
-                solana_program::instruction::AccountMeta::new_readonly(spl_token::id(), false),
-            ],
-        );
-        
-        let tx = Transaction::new_signed_with_payer(
-            &[stake_ix],
-            Some(&payer.pubkey()),
-            &[&payer, &miner],
-            svm.latest_blockhash(),
-        );
-        let _ = svm.send_transaction(tx);
-
         // Submit proof
         let proof_hash = [9u8; 32];
         let submit_ix = Instruction::new_with_borsh(
             program_id,
-            &TapeInstruction::SubmitProof { epoch_id, proof_hash },
+            &TapeInstruction::SubmitProof { epoch_id, proof_hash, proofs: vec![] },
             vec![
                 solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
                 solana_program::instruction::AccountMeta::new(epoch_pda, false),
                 solana_program::instruction::AccountMeta::new(miner_pda, false),
-                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
             ],
         );
         
@@ -427,10 +928,13 @@ mod tests {
 
         // Read epoch back
         let epoch_acct = svm.get_account(&epoch_pda).expect("epoch present");
-        let epoch_rec = EpochRecord::try_from_slice(&epoch_acct.data).expect("deserialize epoch");
+        let epoch_rec = decode_account::<EpochRecord>(&epoch_acct.data);
         assert_eq!(epoch_rec.status, EpochStatus::Submitted);
         
         // ---- Finalize Epoch ----
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp += ConfigParams::default().dispute_window_secs;
+        svm.set_sysvar(&clock);
         let miner_reward_ata = get_associated_token_address(&miner_pub, &tape_mint_pub);
         
         let finalize_ix = Instruction::new_with_borsh(
@@ -440,10 +944,12 @@ mod tests {
                 solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
                 solana_program::instruction::AccountMeta::new(epoch_pda, false),
                 solana_program::instruction::AccountMeta::new(miner_pda, false),
-                solana_program::instruction::AccountMeta::new(reward_vault_ata, false),
-                solana_program::instruction::AccountMeta::new(miner_reward_ata, false),
-                solana_program::instruction::AccountMeta::new_readonly(spl_token::id(), false),
                 solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_escrow_pda(&program_id, &object_pda).0, false),
+                solana_program::instruction::AccountMeta::new(miner_ata, false),
+                solana_program::instruction::AccountMeta::new_readonly(spl_token::id(), false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
             ],
         );
         
@@ -458,7 +964,7 @@ mod tests {
         // Claim rewards
         let claim_ix = Instruction::new_with_borsh(
             program_id,
-            &TapeInstruction::Claim {},
+            &TapeInstruction::ClaimRewards,
             vec![
                 solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
                 solana_program::instruction::AccountMeta::new(miner_pda, false),
@@ -484,10 +990,12 @@ mod tests {
             vec![
                 solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
                 solana_program::instruction::AccountMeta::new(miner_pda, false),
-                solana_program::instruction::AccountMeta::new(reward_vault_ata, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
                 solana_program::instruction::AccountMeta::new(miner_reward_ata, false),
                 solana_program::instruction::AccountMeta::new_readonly(spl_token::id(), false),
                 solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
             ],
         );
         
@@ -505,4 +1013,3242 @@ mod tests {
         assert!(svm.get_account(&epoch_pda).is_some());
         assert!(svm.get_account(&miner_pda).is_some());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_epoch_ids_and_challenges_are_not_chosen_by_callers() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+
+        let (global_pda, _) = derive_global_pda(&program_id);
+        seed_global(&mut svm, &program_id, &default_global(payer.pubkey(), Pubkey::new_unique()));
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        // Seed a registered object directly so the test only exercises CreateEpoch.
+        let owner = Pubkey::new_unique();
+        let object_id: u128 = 7;
+        let chunk = vec![5u8; 64];
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner, object_id);
+        let object = ObjectRecord {
+            owner,
+            creator: owner,
+            object_id,
+            commitment: leaf_hash(&chunk),
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 10,
+            replication_factor: 1,
+            assigned_miners: assigned(payer.pubkey()),
+            epoch_payment: 0,
+            deposit: 0,
+            epochs_opened: [0; 4],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let miner_pda = seed_miner(&mut svm, &program_id, &payer.pubkey(), 0);
+        let create_epoch_ix = |epoch_pda: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+
+        // The old layout carried a caller-chosen epoch id after object_id.
+        let mut legacy_data = vec![2u8];
+        legacy_data.extend_from_slice(&object_id.to_le_bytes());
+        legacy_data.extend_from_slice(&5u128.to_le_bytes());
+        let mut legacy_ix = create_epoch_ix(derive_epoch_pda(&program_id, &object_pda, &payer.pubkey(), 5).0);
+        legacy_ix.data = legacy_data;
+        assert!(send(&mut svm, legacy_ix, &[&payer]).is_err());
+
+        // The id is the replica's next one, so another epoch's address is refused.
+        let (skipped_pda, _) = derive_epoch_pda(&program_id, &object_pda, &payer.pubkey(), 5);
+        assert_eq!(custom_error(send(&mut svm, create_epoch_ix(skipped_pda), &[&payer])), Some(22));
+
+        let epoch_id: u128 = 1;
+        let (epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &payer.pubkey(), epoch_id);
+        let mut clock: Clock = svm.get_sysvar();
+        clock.slot = 40;
+        svm.set_sysvar(&clock);
+        send(&mut svm, create_epoch_ix(epoch_pda), &[&payer]).unwrap();
+
+        // The challenges are drawn from a slot past the one the epoch opened in.
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch_rec.epoch_id, epoch_id);
+        assert_eq!(epoch_rec.seed_slot, 42);
+        assert_eq!(epoch_rec.miner, payer.pubkey());
+        assert_eq!(epoch_rec.status, EpochStatus::Open);
+
+        let submit_ix = |proof_hash: [u8; 32]| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::SubmitProof {
+                epoch_id,
+                proof_hash,
+                proofs: (0..2).map(|_| ChunkProof { chunk: chunk.clone(), path: vec![] }).collect(),
+            },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+            ],
+        );
+
+        // Before the seed slot is produced there is nothing to prove against.
+        svm.set_sysvar(&SlotHashes::new(&[(41, Hash::new_unique()), (40, Hash::new_unique())]));
+        assert_eq!(custom_error(send(&mut svm, submit_ix([0u8; 32]), &[&payer])), Some(73));
+
+        // Once it has aged out of SlotHashes the epoch can no longer be proven.
+        svm.set_sysvar(&SlotHashes::new(&[(600, Hash::new_unique()), (90, Hash::new_unique())]));
+        assert_eq!(custom_error(send(&mut svm, submit_ix([0u8; 32]), &[&payer])), Some(74));
+
+        // A skipped seed slot falls through to the next produced one.
+        let seed = Hash::new_unique();
+        svm.set_sysvar(&SlotHashes::new(&[(44, Hash::new_unique()), (43, seed), (41, Hash::new_unique())]));
+        let digest = hashv(&[seed.as_ref(), object_pda.as_ref(), payer.pubkey().as_ref(), &epoch_id.to_le_bytes()]);
+        let nonce = u64::from_le_bytes(digest.to_bytes()[..8].try_into().unwrap());
+        send(&mut svm, submit_ix(response_digest(nonce, &[chunk.clone(), chunk.clone()])), &[&payer]).unwrap();
+
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch_rec.nonce, nonce);
+        assert_eq!(epoch_rec.challenges, [0, 0]);
+        assert_eq!(epoch_rec.status, EpochStatus::Submitted);
+    }
+
+    #[test]
+    fn test_rewards_are_credited_on_finalize_and_paid_on_claim() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+
+        let emission_cap = 7_000_000u64;
+        let vault_balance = 5_000_000u64;
+        let reward = 1_000_000u64;
+
+        let (global_pda, _) = derive_global_pda(&program_id);
+        let mint = Pubkey::new_unique();
+        let reward_vault = Pubkey::new_unique();
+        let global_state = GlobalState {
+            reward_vault,
+            emission_cap,
+            epoch_reward: reward,
+            ..default_global(payer.pubkey(), mint)
+        };
+        seed_global(&mut svm, &program_id, &global_state);
+        let config_pda = seed_config(&mut svm, &program_id);
+        set_token_account(&mut svm, reward_vault, &mint, &global_pda, vault_balance);
+
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+        let (miner_pda, miner_bump) = derive_miner_pda(&program_id, &miner.pubkey());
+        let miner_acc = MinerAccount {
+            miner: miner.pubkey(),
+            operator: miner.pubkey(),
+            stake: 0,
+            pending_rewards: 0,
+            reputation: 100,
+            unstake_ts: 0,
+            metadata: [0u8; 64],
+            open_epochs: 0,
+            assignments: 0,
+            bump: miner_bump,
+        };
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&miner_acc));
+        let miner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 0);
+
+        let (object_id, epoch_id) = (1u128, 1u128);
+        let owner = Pubkey::new_unique();
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner, object_id);
+        let object = ObjectRecord {
+            owner,
+            creator: owner,
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 10,
+            replication_factor: 1,
+            assigned_miners: assigned(miner.pubkey()),
+            epoch_payment: 0,
+            deposit: 0,
+            epochs_opened: [1, 0, 0, 0],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let (epoch_pda, epoch_bump) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let epoch = EpochRecord {
+            object_id,
+            object: object_pda,
+            epoch_id,
+            nonce: 0,
+            seed_slot: 0,
+            deadline_ts: 0,
+            challenges: [0; 2],
+            miner: miner.pubkey(),
+            solver: Some(miner.pubkey()),
+            proof_hash: [0u8; 32],
+            submitted_at: 0,
+            status: EpochStatus::Submitted,
+            proven: 2,
+            reward,
+            payment: 0,
+            version: 0,
+            opener: miner.pubkey(),
+            bump: epoch_bump,
+        };
+        set_account_data(&mut svm, epoch_pda, program_id, account_bytes(&epoch));
+
+        let finalize_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::FinalizeEpoch { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_escrow_pda(&program_id, &object_pda).0, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        // Challengers get the whole dispute window before the epoch settles.
+        assert_eq!(custom_error(send(&mut svm, finalize_ix.clone(), &[&payer])), Some(6));
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = ConfigParams::default().dispute_window_secs;
+        svm.set_sysvar(&clock);
+        send(&mut svm, finalize_ix.clone(), &[&payer]).unwrap();
+
+        // Finalisation credits the reward and moves no tokens.
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        let global_state: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(miner_acc.pending_rewards, reward);
+        assert_eq!(global_state.total_allocated, reward);
+        assert_eq!(global_state.total_minted, 0);
+        assert_eq!(global_state.emission_cap, emission_cap);
+        assert_eq!(token_balance(&svm, &reward_vault), vault_balance);
+        assert_eq!(token_balance(&svm, &miner_token), 0);
+
+        // A finalised epoch cannot be credited twice.
+        assert!(send(&mut svm, finalize_ix, &[&payer]).is_err());
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.pending_rewards, reward);
+
+        let claim_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ClaimRewards,
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(reward_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            ],
+        );
+        send(&mut svm, claim_ix.clone(), &[&miner]).unwrap();
+
+        // Claiming pays out exactly the pending amount.
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.pending_rewards, 0);
+        assert_eq!(token_balance(&svm, &miner_token), reward);
+        assert_eq!(token_balance(&svm, &reward_vault), vault_balance - reward);
+
+        // Nothing is left to claim.
+        assert!(send(&mut svm, claim_ix, &[&miner]).is_err());
+        assert_eq!(token_balance(&svm, &miner_token), reward);
+    }
+
+    #[test]
+    fn test_mint_mode_mints_rewards_on_claim() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let reward = 1_000_000u64;
+        let (global_pda, _) = derive_global_pda(&program_id);
+
+        // Reward mint whose authority is the global PDA, as set up by initialize.
+        let mint = Pubkey::new_unique();
+        set_mint(&mut svm, mint, &global_pda);
+
+        let global_state = GlobalState {
+            emission_mode: 1,
+            total_allocated: reward,
+            epoch_reward: reward,
+            ..default_global(Pubkey::new_unique(), mint)
+        };
+        seed_global(&mut svm, &program_id, &global_state);
+
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+        let (miner_pda, miner_bump) = derive_miner_pda(&program_id, &miner.pubkey());
+        let miner_acc = MinerAccount {
+            miner: miner.pubkey(),
+            operator: miner.pubkey(),
+            stake: 0,
+            pending_rewards: reward,
+            reputation: 100,
+            unstake_ts: 0,
+            metadata: [0u8; 64],
+            open_epochs: 0,
+            assignments: 0,
+            bump: miner_bump,
+        };
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&miner_acc));
+        let miner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 0);
+
+        let claim_ix = |source: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ClaimRewards,
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(source, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+            ],
+        );
+
+        // The vault is not a valid reward source in Mint mode.
+        assert!(send(&mut svm, claim_ix(global_state.reward_vault), &[&miner]).is_err());
+
+        send(&mut svm, claim_ix(mint), &[&miner]).unwrap();
+
+        // total_minted follows the real mint supply.
+        let global_state: GlobalState = read_account(&svm, &global_pda);
+        let supply = Mint::unpack(&svm.get_account(&mint).unwrap().data).unwrap().supply;
+        assert_eq!(token_balance(&svm, &miner_token), reward);
+        assert_eq!(supply, reward);
+        assert_eq!(global_state.total_minted, supply);
+        assert_eq!(global_state.total_allocated, reward);
+    }
+
+    #[test]
+    fn test_epoch_reward_follows_decay_schedule() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+        let global_state = default_global(payer.pubkey(), Pubkey::new_unique());
+        let global_pda = seed_global(&mut svm, &program_id, &global_state);
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let owner = Pubkey::new_unique();
+        let object_id: u128 = 3;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner, object_id);
+        let object = ObjectRecord {
+            owner,
+            creator: owner,
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 10,
+            replication_factor: 1,
+            assigned_miners: assigned(payer.pubkey()),
+            epoch_payment: 0,
+            deposit: 0,
+            epochs_opened: [0; 4],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let miner_pda = seed_miner(&mut svm, &program_id, &payer.pubkey(), 0);
+
+        // Two full decay periods have passed since last_decay_at.
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = 2 * 86_400 + 5;
+        svm.set_sysvar(&clock);
+
+        let epoch_id: u128 = 1;
+        let (epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &payer.pubkey(), epoch_id);
+        let create_epoch_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, create_epoch_ix, &[&payer]).unwrap();
+
+        // 1_000_000 -> 850_000 -> 722_500
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch_rec.reward, 722_500);
+        let global_state: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global_state.epoch_reward, 722_500);
+        assert_eq!(global_state.last_decay_at, 2 * 86_400);
+    }
+
+    #[test]
+    fn test_missed_deadline_is_slashed_permissionlessly() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        let (treasury, _) = derive_treasury_pda(&program_id);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 10_000);
+        set_token_account(&mut svm, treasury, &mint, &global_pda, 0);
+
+        let owner = Pubkey::new_unique();
+        let object_id: u128 = 9;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner, object_id);
+        let object = ObjectRecord {
+            owner,
+            creator: owner,
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 10,
+            replication_factor: 1,
+            assigned_miners: assigned(miner.pubkey()),
+            epoch_payment: 0,
+            deposit: 0,
+            epochs_opened: [0; 4],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 10_000);
+
+        let epoch_id: u128 = 1;
+        let (epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let create_epoch_ix = |caller: Pubkey, epoch_id: u128| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(caller, true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, create_epoch_ix(miner.pubkey(), epoch_id), &[&miner]).unwrap();
+
+        // Nobody, the miner included, can open the next epoch before it is due.
+        assert_eq!(custom_error(send(&mut svm, create_epoch_ix(payer.pubkey(), 2), &[&payer])), Some(70));
+        assert_eq!(custom_error(send(&mut svm, create_epoch_ix(miner.pubkey(), 2), &[&miner])), Some(70));
+
+        let mark_missed_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::MarkMissed { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+
+        // Nothing can be marked before the deadline.
+        assert_eq!(custom_error(send(&mut svm, mark_missed_ix.clone(), &[&payer])), Some(37));
+
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = epoch_rec.deadline_ts + 1;
+        svm.set_sysvar(&clock);
+
+        // A late submission is rejected and leaves the epoch open.
+        let submit_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::SubmitProof { epoch_id, proof_hash: [0u8; 32], proofs: vec![] },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, submit_ix, &[&miner])), Some(1));
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch_rec.status, EpochStatus::Open);
+
+        // Anyone can mark the epoch missed once the deadline has passed.
+        let result = send(&mut svm, mark_missed_ix.clone(), &[&payer]);
+        assert!(result.is_ok());
+        assert_eq!(
+            logged_events(&result),
+            vec![ContractEvent::EpochMissed {
+                object_id,
+                epoch_id,
+                miner: miner.pubkey().to_bytes(),
+                slashed: 500,
+                timestamp: epoch_rec.deadline_ts + 1,
+            }]
+        );
+
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch_rec.status, EpochStatus::Missed);
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 9_500);
+        assert_eq!(miner_acc.reputation, 95);
+
+        // The slashed tokens leave the stake vault for the treasury.
+        assert_eq!(token_balance(&svm, &stake_vault), miner_acc.stake);
+        assert_eq!(token_balance(&svm, &treasury), 500);
+
+        // The penalty is applied once.
+        assert_eq!(custom_error(send(&mut svm, mark_missed_ix.clone(), &[&payer])), Some(34));
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 9_500);
+
+        // A miner that stops opening epochs is still slashed: once its next
+        // epoch is due anyone can open it, paying the rent as the opener.
+        send(&mut svm, create_epoch_ix(payer.pubkey(), 2), &[&payer]).unwrap();
+        let (epoch_2, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), 2);
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_2);
+        assert_eq!(epoch_rec.opener, payer.pubkey());
+        assert_eq!(epoch_rec.miner, miner.pubkey());
+
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = epoch_rec.deadline_ts + 1;
+        svm.set_sysvar(&clock);
+        let mut mark_missed_ix = mark_missed_ix;
+        mark_missed_ix.data = borsh::to_vec(&TapeInstruction::MarkMissed { epoch_id: 2 }).unwrap();
+        mark_missed_ix.accounts[1].pubkey = epoch_2;
+        send(&mut svm, mark_missed_ix, &[&payer]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 9_025);
+        assert_eq!(miner_acc.reputation, 90);
+
+        // Closing the epoch returns the rent to the opener, not the miner.
+        let close_epoch_ix = |recipient: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CloseEpoch { epoch_id: 2 },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_2, false),
+                solana_program::instruction::AccountMeta::new(recipient, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), 2).0, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, close_epoch_ix(miner.pubkey()), &[&payer])), Some(32));
+        send(&mut svm, close_epoch_ix(payer.pubkey()), &[&payer]).unwrap();
+        assert!(svm.get_account(&epoch_2).map_or(true, |a| a.lamports == 0));
+    }
+
+    #[test]
+    fn test_miners_deregister_once_their_epochs_are_settled() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        let (treasury, _) = derive_treasury_pda(&program_id);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 0);
+        set_token_account(&mut svm, treasury, &mint, &global_pda, 0);
+
+        let owner = Pubkey::new_unique();
+        let object_id: u128 = 12;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner, object_id);
+        let object = ObjectRecord {
+            owner,
+            creator: owner,
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 10,
+            replication_factor: 1,
+            assigned_miners: assigned(miner.pubkey()),
+            epoch_payment: 0,
+            deposit: 0,
+            epochs_opened: [0; 4],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        // The miner holds no stake, so only its open epoch stands in the way
+        // of deregistering.
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 0);
+        let epoch_id: u128 = 1;
+        let (epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let create_epoch_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, create_epoch_ix, &[&miner]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.open_epochs, 1);
+
+        let deregister_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::DeregisterMiner,
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, deregister_ix.clone(), &[&miner])), Some(66));
+
+        // Missing the epoch settles it. The penalty it costs does not keep the
+        // miner from leaving.
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = epoch_rec.deadline_ts + 1;
+        svm.set_sysvar(&clock);
+        let mark_missed_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::MarkMissed { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, mark_missed_ix, &[&payer]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.open_epochs, 0);
+        assert_eq!(miner_acc.reputation, 95);
+        send(&mut svm, deregister_ix, &[&miner]).unwrap();
+        assert!(svm.get_account(&miner_pda).map_or(true, |a| a.lamports == 0));
+
+        // A miner with nothing owed deregisters. Its vault is closed too:
+        // stray tokens go to the treasury, the rent to the miner.
+        let idle = Keypair::new();
+        svm.airdrop(&idle.pubkey(), 1_000_000_000).unwrap();
+        let idle_pda = seed_miner(&mut svm, &program_id, &idle.pubkey(), 0);
+        let (idle_vault, _) = derive_stake_vault_pda(&program_id, &idle.pubkey());
+        set_token_account(&mut svm, idle_vault, &mint, &global_pda, 5);
+        let deregister_ix = |vault: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::DeregisterMiner,
+            vec![
+                solana_program::instruction::AccountMeta::new(idle.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(idle_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+                solana_program::instruction::AccountMeta::new(vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, deregister_ix(stake_vault), &[&idle])), Some(64));
+        let treasury_balance = token_balance(&svm, &treasury);
+        let idle_lamports = svm.get_account(&idle.pubkey()).unwrap().lamports;
+        let vault_rent = svm.get_account(&idle_vault).unwrap().lamports;
+        let miner_rent = svm.get_account(&idle_pda).unwrap().lamports;
+        send(&mut svm, deregister_ix(idle_vault), &[&payer, &idle]).unwrap();
+        assert!(svm.get_account(&idle_pda).map_or(true, |a| a.lamports == 0));
+        assert!(svm.get_account(&idle_vault).map_or(true, |a| a.lamports == 0));
+        assert_eq!(token_balance(&svm, &treasury), treasury_balance + 5);
+        assert_eq!(svm.get_account(&idle.pubkey()).unwrap().lamports, idle_lamports + vault_rent + miner_rent);
+
+        // Leaving the registry takes both out of future assignment draws.
+        let registry: MinerRegistry = read_account(&svm, &derive_registry_pda(&program_id).0);
+        assert_eq!(registry.count, 0);
+    }
+
+    fn leaf_hash(chunk: &[u8]) -> [u8; 32] {
+        hashv(&[&[0u8], chunk]).to_bytes()
+    }
+
+    fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        hashv(&[&[1u8], left, right]).to_bytes()
+    }
+
+    fn response_digest(nonce: u64, chunks: &[Vec<u8>]) -> [u8; 32] {
+        let mut acc = hashv(&[&nonce.to_le_bytes()]).to_bytes();
+        for chunk in chunks {
+            acc = hashv(&[&acc, chunk]).to_bytes();
+        }
+        acc
+    }
+
+    /// Publishes a hash for the epoch's seed slot and returns the nonce and
+    /// chunk indices SubmitProof draws from it.
+    fn reveal_challenges(svm: &mut LiteSVM, epoch: &EpochRecord, chunk_count: u64) -> (u64, [u64; 2]) {
+        let seed = Hash::new_unique();
+        svm.set_sysvar(&SlotHashes::new(&[(epoch.seed_slot, seed)]));
+        let digest = hashv(&[seed.as_ref(), epoch.object.as_ref(), epoch.miner.as_ref(), &epoch.epoch_id.to_le_bytes()]).to_bytes();
+        let nonce = u64::from_le_bytes(digest[..8].try_into().unwrap());
+        let challenges = std::array::from_fn(|i| {
+            let digest = hashv(&[&nonce.to_le_bytes(), &epoch.epoch_id.to_le_bytes(), &[i as u8]]).to_bytes();
+            u64::from_le_bytes(digest[..8].try_into().unwrap()) % chunk_count
+        });
+        (nonce, challenges)
+    }
+
+    #[test]
+    fn test_challenges_ask_for_chunks_the_proof_did_not_cover() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        let owner = Keypair::new();
+        let miner = Keypair::new();
+        let challenger = Keypair::new();
+        for key in [&payer, &owner, &miner, &challenger] {
+            svm.airdrop(&key.pubkey(), 1_000_000_000).unwrap();
+        }
+
+        let reward = 1_000_000u64;
+        let bond = 100_000u64;
+        let (global_pda, _) = derive_global_pda(&program_id);
+        let mint = Pubkey::new_unique();
+        set_mint(&mut svm, mint, &global_pda);
+        let global_state = GlobalState {
+            epoch_reward: reward,
+            ..default_global(payer.pubkey(), mint)
+        };
+        seed_global(&mut svm, &program_id, &global_state);
+        let config_pda = seed_config(&mut svm, &program_id);
+        svm.set_sysvar(&SlotHashes::new(&[(42, Hash::new_unique())]));
+
+        let (bond_vault, _) = derive_bond_vault_pda(&program_id);
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        let challenger_token = Pubkey::new_unique();
+        let miner_token = Pubkey::new_unique();
+        let owner_token = Pubkey::new_unique();
+        let (treasury, _) = derive_treasury_pda(&program_id);
+        set_token_account(&mut svm, treasury, &mint, &global_pda, 0);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 10_000);
+        set_token_account(&mut svm, challenger_token, &mint, &challenger.pubkey(), 3 * bond);
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 0);
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 100_000);
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 10_000);
+
+        // A four-chunk object committed to as a Merkle tree, assigned to the
+        // miner by RegisterObject.
+        let chunks: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 64]).collect();
+        let leaves: Vec<[u8; 32]> = chunks.iter().map(|c| leaf_hash(c)).collect();
+        let nodes = [node_hash(&leaves[0], &leaves[1]), node_hash(&leaves[2], &leaves[3])];
+        let root = node_hash(&nodes[0], &nodes[1]);
+        let chunk_proof = |index: u64| {
+            let index = index as usize;
+            ChunkProof { chunk: chunks[index].clone(), path: vec![leaves[index ^ 1], nodes[(index >> 1) ^ 1]] }
+        };
+
+        let object_id: u128 = 5;
+        let (object_pda, _) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        let register_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::RegisterObject {
+                commitment: root,
+                proof_type: 0,
+                size: 256,
+                chunk_count: 4,
+                retention_epochs: 10,
+                object_id,
+                replication_factor: 1,
+            },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+            ],
+        );
+        send(&mut svm, register_ix, &[&owner]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        let payment = object.epoch_payment;
+
+        // Opens the replica's next epoch and proves the chunks SubmitProof draws.
+        let prove_epoch = |svm: &mut LiteSVM, epoch_id: u128| -> (Pubkey, [u64; 2]) {
+            let (epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+            let create_epoch_ix = Instruction::new_with_borsh(
+                program_id,
+                &TapeInstruction::CreateEpoch { object_id },
+                vec![
+                    solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                    solana_program::instruction::AccountMeta::new(object_pda, false),
+                    solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                    solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                    solana_program::instruction::AccountMeta::new(global_pda, false),
+                    solana_program::instruction::AccountMeta::new(miner_pda, false),
+                    solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                ],
+            );
+            send(svm, create_epoch_ix, &[&miner]).unwrap();
+
+            let epoch_rec: EpochRecord = read_account(svm, &epoch_pda);
+            let (nonce, challenges) = reveal_challenges(svm, &epoch_rec, 4);
+            let submit_ix = Instruction::new_with_borsh(
+                program_id,
+                &TapeInstruction::SubmitProof {
+                    epoch_id,
+                    proof_hash: response_digest(nonce, &challenges.map(|i| chunks[i as usize].clone())),
+                    proofs: challenges.iter().map(|&i| chunk_proof(i)).collect(),
+                },
+                vec![
+                    solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                    solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                    solana_program::instruction::AccountMeta::new(miner_pda, false),
+                    solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                    solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+                ],
+            );
+            send(svm, submit_ix, &[&miner]).unwrap();
+            (epoch_pda, challenges)
+        };
+        let challenge_ix = |epoch_pda: Pubkey, epoch_id: u128, chunk_index: u64| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ChallengeProof { epoch_id, chunk_index },
+            vec![
+                solana_program::instruction::AccountMeta::new(challenger.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new(challenger_token, false),
+                solana_program::instruction::AccountMeta::new(bond_vault, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+            ],
+        );
+        let answer_ix = |caller: Pubkey, epoch_pda: Pubkey, epoch_id: u128, proof: ChunkProof| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::AnswerChallenge { epoch_id, proof },
+            vec![
+                solana_program::instruction::AccountMeta::new(caller, true),
+                solana_program::instruction::AccountMeta::new_readonly(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        let resolve_ix = |epoch_pda: Pubkey, epoch_id: u128| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ResolveChallenge { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(challenger.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new(bond_vault, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(challenger_token, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+
+        // Only a chunk of the object that the submission did not already
+        // prove can be challenged.
+        let (first_epoch, proven) = prove_epoch(&mut svm, 1);
+        let unproven = (0..4).find(|index| !proven.contains(index)).unwrap();
+        assert_eq!(custom_error(send(&mut svm, challenge_ix(first_epoch, 1, 4), &[&challenger])), Some(77));
+        assert_eq!(custom_error(send(&mut svm, challenge_ix(first_epoch, 1, proven[0]), &[&challenger])), Some(76));
+
+        // Bonds only move through the bond vault PDA.
+        let mut spoofed = challenge_ix(first_epoch, 1, unproven);
+        spoofed.accounts[5].pubkey = escrow;
+        assert_eq!(custom_error(send(&mut svm, spoofed, &[&challenger])), Some(65));
+
+        send(&mut svm, challenge_ix(first_epoch, 1, unproven), &[&challenger]).unwrap();
+        assert_eq!(token_balance(&svm, &bond_vault), bond);
+        let (challenge_pda, _) = derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), 1);
+        let challenge: ChallengeRecord = read_account(&svm, &challenge_pda);
+        assert_eq!(challenge.challenger, challenger.pubkey());
+        assert_eq!(challenge.chunk_index, unproven);
+        assert_eq!(challenge.status, ChallengeStatus::Pending);
+
+        // Only one challenge per epoch.
+        let other = (0..4).rev().find(|index| !proven.contains(index)).unwrap();
+        assert!(send(&mut svm, challenge_ix(first_epoch, 1, other), &[&challenger]).is_err());
+
+        // Only the solver can answer, and only with the challenged chunk.
+        assert_eq!(custom_error(send(&mut svm, answer_ix(challenger.pubkey(), first_epoch, 1, chunk_proof(unproven)), &[&challenger])), Some(19));
+        assert_eq!(custom_error(send(&mut svm, answer_ix(miner.pubkey(), first_epoch, 1, chunk_proof(proven[0])), &[&miner])), Some(3));
+        let mut forged = chunk_proof(unproven);
+        forged.chunk = vec![0xff; 64];
+        assert_eq!(custom_error(send(&mut svm, answer_ix(miner.pubkey(), first_epoch, 1, forged), &[&miner])), Some(3));
+
+        // An unanswered challenge cannot be upheld while the window is open,
+        // and once it has closed the answer comes too late.
+        assert_eq!(custom_error(send(&mut svm, resolve_ix(first_epoch, 1), &[&challenger])), Some(6));
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp += 3_600;
+        svm.set_sysvar(&clock);
+        assert_eq!(custom_error(send(&mut svm, answer_ix(miner.pubkey(), first_epoch, 1, chunk_proof(unproven)), &[&miner])), Some(78));
+
+        let mut spoofed = resolve_ix(first_epoch, 1);
+        spoofed.accounts[6].pubkey = stake_vault;
+        assert_eq!(custom_error(send(&mut svm, spoofed, &[&challenger])), Some(65));
+        assert_eq!(token_balance(&svm, &stake_vault), 10_000);
+
+        send(&mut svm, resolve_ix(first_epoch, 1), &[&challenger]).unwrap();
+        let epoch_rec: EpochRecord = read_account(&svm, &first_epoch);
+        assert_eq!(epoch_rec.status, EpochStatus::Slashed);
+        let challenge: ChallengeRecord = read_account(&svm, &challenge_pda);
+        assert_eq!(challenge.status, ChallengeStatus::Upheld);
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 9_000);
+        assert_eq!(miner_acc.pending_rewards, 0);
+        let params = ConfigParams::default();
+        let reputation = 100 - params.challenge_reputation_penalty - params.slash_reputation_penalty;
+        assert_eq!(miner_acc.reputation, reputation);
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.refundable, payment);
+
+        // The bond comes back with half of the slashed stake; the other half
+        // goes to the treasury and the vault still backs the recorded stake.
+        assert_eq!(token_balance(&svm, &bond_vault), 0);
+        assert_eq!(token_balance(&svm, &stake_vault), miner_acc.stake);
+        assert_eq!(token_balance(&svm, &challenger_token), 3 * bond + 500);
+        assert_eq!(token_balance(&svm, &treasury), 500);
+
+        // A miner that still holds the object answers the challenge and is
+        // paid for the epoch.
+        let (second_epoch, proven) = prove_epoch(&mut svm, 2);
+        let unproven = (0..4).find(|index| !proven.contains(index)).unwrap();
+        send(&mut svm, challenge_ix(second_epoch, 2, unproven), &[&challenger]).unwrap();
+        send(&mut svm, answer_ix(miner.pubkey(), second_epoch, 2, chunk_proof(unproven)), &[&miner]).unwrap();
+        let (challenge_pda, _) = derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), 2);
+        let challenge: ChallengeRecord = read_account(&svm, &challenge_pda);
+        assert_eq!(challenge.status, ChallengeStatus::Answered);
+        assert_eq!(custom_error(send(&mut svm, answer_ix(miner.pubkey(), second_epoch, 2, chunk_proof(unproven)), &[&miner])), Some(38));
+
+        // Answered challenges are rejected without waiting out the window.
+        send(&mut svm, resolve_ix(second_epoch, 2), &[&challenger]).unwrap();
+
+        let epoch_rec: EpochRecord = read_account(&svm, &second_epoch);
+        assert_eq!(epoch_rec.status, EpochStatus::Finalized);
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 9_000);
+        assert_eq!(miner_acc.pending_rewards, reward);
+        // A rejected challenge costs the miner no reputation.
+        assert_eq!(miner_acc.reputation, reputation);
+        let global_state: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global_state.total_allocated, reward);
+        assert_eq!(token_balance(&svm, &miner_token), payment);
+
+        // The failed challenger forfeits the bond to the treasury.
+        let challenge: ChallengeRecord = read_account(&svm, &challenge_pda);
+        assert_eq!(challenge.status, ChallengeStatus::Rejected);
+        assert_eq!(token_balance(&svm, &bond_vault), 0);
+        assert_eq!(token_balance(&svm, &treasury), 500 + bond);
+        assert_eq!(token_balance(&svm, &challenger_token), 2 * bond + 500);
+
+        // Closing a settled epoch closes its challenge too, returning that
+        // rent to the challenger.
+        let close_epoch_ix = |epoch_pda: Pubkey, epoch_id: u128, challenge: Pubkey, rent_to: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CloseEpoch { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), false),
+                solana_program::instruction::AccountMeta::new(challenge, false),
+                solana_program::instruction::AccountMeta::new(rent_to, false),
+            ],
+        );
+        let (challenge_1, _) = derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), 1);
+        assert_eq!(custom_error(send(&mut svm, close_epoch_ix(first_epoch, 1, challenge_pda, challenger.pubkey()), &[&payer])), Some(24));
+        assert_eq!(custom_error(send(&mut svm, close_epoch_ix(first_epoch, 1, challenge_1, miner.pubkey()), &[&payer])), Some(32));
+
+        let challenger_lamports = svm.get_account(&challenger.pubkey()).unwrap().lamports;
+        let challenge_rent = svm.get_account(&challenge_1).unwrap().lamports;
+        send(&mut svm, close_epoch_ix(first_epoch, 1, challenge_1, challenger.pubkey()), &[&payer]).unwrap();
+        assert!(svm.get_account(&first_epoch).map_or(true, |a| a.lamports == 0));
+        assert!(svm.get_account(&challenge_1).map_or(true, |a| a.lamports == 0));
+        assert_eq!(svm.get_account(&challenger.pubkey()).unwrap().lamports, challenger_lamports + challenge_rent);
+    }
+
+    #[test]
+    fn test_admin_slash_moves_stake_to_treasury() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let admin = Keypair::new();
+        svm.airdrop(&admin.pubkey(), 1_000_000_000).unwrap();
+        let intruder = Keypair::new();
+        svm.airdrop(&intruder.pubkey(), 1_000_000_000).unwrap();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(admin.pubkey(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let miner = Pubkey::new_unique();
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner);
+        let (treasury, _) = derive_treasury_pda(&program_id);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 1_000);
+        set_token_account(&mut svm, treasury, &mint, &global_pda, 0);
+
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner, 1_000);
+
+        let slash_ix = |signer: Pubkey, amount: u64| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Slash { miner, amount },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer, true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+
+        assert_eq!(custom_error(send(&mut svm, slash_ix(intruder.pubkey(), 300), &[&intruder])), Some(53));
+        send(&mut svm, slash_ix(admin.pubkey(), 300), &[&admin]).unwrap();
+
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 700);
+        assert_eq!(token_balance(&svm, &stake_vault), miner_acc.stake);
+        assert_eq!(token_balance(&svm, &treasury), 300);
+
+        // Slashing is capped at the recorded stake.
+        send(&mut svm, slash_ix(admin.pubkey(), 5_000), &[&admin]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 0);
+        assert_eq!(token_balance(&svm, &stake_vault), 0);
+        assert_eq!(token_balance(&svm, &treasury), 1_000);
+    }
+
+    #[test]
+    fn test_admin_rotation_pause_and_quorum() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let admin = Keypair::new();
+        let next_admin = Keypair::new();
+        let cosigner = Keypair::new();
+        let outsider = Keypair::new();
+        for key in [&admin, &next_admin, &cosigner, &outsider] {
+            svm.airdrop(&key.pubkey(), 1_000_000_000).unwrap();
+        }
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(admin.pubkey(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let miner = Pubkey::new_unique();
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner);
+        let (treasury, _) = derive_treasury_pda(&program_id);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 1_000);
+        set_token_account(&mut svm, treasury, &mint, &global_pda, 0);
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner, 1_000);
+
+        let governance_ix = |signer: &Keypair, instruction: TapeInstruction, approvers: &[&Keypair]| {
+            let mut accounts = vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+            ];
+            accounts.extend(approvers.iter().map(|a| solana_program::instruction::AccountMeta::new_readonly(a.pubkey(), true)));
+            Instruction::new_with_borsh(program_id, &instruction, accounts)
+        };
+        let slash_ix = |signer: &Keypair, approvers: &[&Keypair]| {
+            let mut accounts = vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ];
+            accounts.extend(approvers.iter().map(|a| solana_program::instruction::AccountMeta::new_readonly(a.pubkey(), true)));
+            Instruction::new_with_borsh(program_id, &TapeInstruction::Slash { miner, amount: 100 }, accounts)
+        };
+
+        // Rotation is proposed by the admin and takes effect when accepted.
+        let propose = |signer: &Keypair| governance_ix(signer, TapeInstruction::ProposeAdmin { new_admin: next_admin.pubkey() }, &[]);
+        assert_eq!(custom_error(send(&mut svm, propose(&outsider), &[&outsider])), Some(16));
+        send(&mut svm, propose(&admin), &[&admin]).unwrap();
+        let global: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global.admin, admin.pubkey());
+        assert_eq!(global.pending_admin, Some(next_admin.pubkey()));
+
+        assert_eq!(custom_error(send(&mut svm, governance_ix(&outsider, TapeInstruction::AcceptAdmin, &[]), &[&outsider])), Some(52));
+        let result = send(&mut svm, governance_ix(&next_admin, TapeInstruction::AcceptAdmin, &[]), &[&next_admin]);
+        let clock: Clock = svm.get_sysvar();
+        assert_eq!(logged_events(&result), vec![
+            ContractEvent::AdminChanged {
+                from: admin.pubkey().to_bytes(),
+                to: next_admin.pubkey().to_bytes(),
+                timestamp: clock.unix_timestamp,
+            },
+            ContractEvent::AdminSetUpdated {
+                admins: vec![next_admin.pubkey().to_bytes()],
+                threshold: 1,
+                timestamp: clock.unix_timestamp,
+            },
+        ]);
+        // The new admin also takes the old one's seat in the admin set.
+        let global: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global.admin, next_admin.pubkey());
+        assert_eq!(global.pending_admin, None);
+        assert_eq!(global.admins, admin_set(&[next_admin.pubkey()]));
+        assert_eq!(global.admin_count, 1);
+
+        // Only the current admin sets the pause mask; pausing Slash (bit 8)
+        // halts it without touching other instructions.
+        let pause = |signer: &Keypair, paused: u64| governance_ix(signer, TapeInstruction::SetPause { paused }, &[]);
+        assert_eq!(custom_error(send(&mut svm, pause(&admin, u64::MAX), &[&admin])), Some(16));
+        send(&mut svm, pause(&next_admin, 1 << 8), &[&next_admin]).unwrap();
+        assert_eq!(custom_error(send(&mut svm, slash_ix(&admin, &[]), &[&admin])), Some(51));
+
+        let deregister_ix = |accounts: Vec<solana_program::instruction::AccountMeta>| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::DeregisterMiner,
+            accounts,
+        );
+        let outsider_miner = derive_miner_pda(&program_id, &outsider.pubkey()).0;
+        let deregister_accounts = vec![
+            solana_program::instruction::AccountMeta::new(outsider.pubkey(), true),
+            solana_program::instruction::AccountMeta::new(outsider_miner, false),
+            solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+            solana_program::instruction::AccountMeta::new(derive_stake_vault_pda(&program_id, &outsider.pubkey()).0, false),
+            solana_program::instruction::AccountMeta::new(treasury, false),
+        ];
+        assert_eq!(custom_error(send(&mut svm, deregister_ix(deregister_accounts.clone()), &[&outsider])), Some(55));
+        let mut with_global = deregister_accounts.clone();
+        with_global.push(solana_program::instruction::AccountMeta::new_readonly(global_pda, false));
+        with_global.push(solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false));
+        assert_eq!(custom_error(send(&mut svm, deregister_ix(with_global.clone()), &[&outsider])), Some(61));
+
+        send(&mut svm, pause(&next_admin, u64::MAX), &[&next_admin]).unwrap();
+        assert_eq!(custom_error(send(&mut svm, deregister_ix(with_global), &[&outsider])), Some(51));
+        send(&mut svm, pause(&next_admin, 0), &[&next_admin]).unwrap();
+
+        // The admin set is replaced with the approval of the current one,
+        // which the rotated-out key no longer belongs to. Afterwards slashing
+        // needs two distinct members to sign.
+        let set_admins = |signer: &Keypair, threshold: u8, approvers: &[&Keypair]| governance_ix(
+            signer,
+            TapeInstruction::SetAdmins { admins: vec![admin.pubkey(), cosigner.pubkey(), next_admin.pubkey()], threshold },
+            approvers,
+        );
+        assert_eq!(custom_error(send(&mut svm, set_admins(&admin, 0, &[]), &[&admin])), Some(54));
+        assert_eq!(custom_error(send(&mut svm, set_admins(&admin, 4, &[]), &[&admin])), Some(54));
+        assert_eq!(custom_error(send(&mut svm, set_admins(&outsider, 2, &[]), &[&outsider])), Some(53));
+        assert_eq!(custom_error(send(&mut svm, set_admins(&admin, 2, &[]), &[&admin])), Some(53));
+        send(&mut svm, set_admins(&next_admin, 2, &[]), &[&next_admin]).unwrap();
+        let global: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global.admin_count, 3);
+        assert_eq!(global.admin_threshold, 2);
+
+        assert_eq!(custom_error(send(&mut svm, slash_ix(&admin, &[]), &[&admin])), Some(53));
+        assert_eq!(custom_error(send(&mut svm, slash_ix(&admin, &[&outsider]), &[&admin, &outsider])), Some(53));
+        send(&mut svm, slash_ix(&admin, &[&cosigner]), &[&admin, &cosigner]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 900);
+        assert_eq!(token_balance(&svm, &treasury), 100);
+
+        // Handing the role to a member drops the outgoing admin's seat, so its
+        // approval no longer counts towards the quorum.
+        let propose_to = |signer: &Keypair, new_admin: Pubkey| governance_ix(signer, TapeInstruction::ProposeAdmin { new_admin }, &[]);
+        send(&mut svm, propose_to(&next_admin, cosigner.pubkey()), &[&next_admin]).unwrap();
+        send(&mut svm, governance_ix(&cosigner, TapeInstruction::AcceptAdmin, &[]), &[&cosigner]).unwrap();
+        let global: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global.admin, cosigner.pubkey());
+        assert_eq!(global.admins, admin_set(&[admin.pubkey(), cosigner.pubkey()]));
+        assert_eq!(global.admin_count, 2);
+        assert_eq!(custom_error(send(&mut svm, slash_ix(&admin, &[&next_admin]), &[&admin, &next_admin])), Some(53));
+
+        // Dropping another seat would leave fewer admins than the threshold.
+        send(&mut svm, propose_to(&cosigner, admin.pubkey()), &[&cosigner]).unwrap();
+        assert_eq!(custom_error(send(&mut svm, governance_ix(&admin, TapeInstruction::AcceptAdmin, &[]), &[&admin])), Some(54));
+    }
+
+    #[test]
+    fn test_config_updates_are_bounded_and_applied() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let admin = Keypair::new();
+        let outsider = Keypair::new();
+        let miner = Keypair::new();
+        for key in [&admin, &outsider, &miner] {
+            svm.airdrop(&key.pubkey(), 1_000_000_000).unwrap();
+        }
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(admin.pubkey(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let update_ix = |signer: &Keypair, params: ConfigParams| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::UpdateConfig { params },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(config_pda, false),
+            ],
+        );
+
+        // Out-of-bounds values and non-admins are rejected.
+        let params = ConfigParams { unstake_cooldown_secs: 60, base_epoch_reward: 500_000, ..ConfigParams::default() };
+        assert_eq!(custom_error(send(&mut svm, update_ix(&outsider, params.clone()), &[&outsider])), Some(53));
+        let zero_deadline = ConfigParams { epoch_duration_secs: 0, ..params.clone() };
+        assert_eq!(custom_error(send(&mut svm, update_ix(&admin, zero_deadline), &[&admin])), Some(57));
+        let over_slash = ConfigParams { slash_bps: 10_001, ..params.clone() };
+        assert_eq!(custom_error(send(&mut svm, update_ix(&admin, over_slash), &[&admin])), Some(57));
+
+        // A new base reward restarts emission from it.
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = 1_000;
+        svm.set_sysvar(&clock);
+        send(&mut svm, update_ix(&admin, params.clone()), &[&admin]).unwrap();
+        let config: Config = read_account(&svm, &config_pda);
+        assert_eq!(config.params, params);
+        let global: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global.epoch_reward, 500_000);
+        assert_eq!(global.last_decay_at, 1_000);
+
+        // Staking now locks for the configured cooldown.
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 0);
+        let miner_token = Pubkey::new_unique();
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 100);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 0);
+        let stake_ix = |config: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Stake { amount: 100 },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+            ],
+        );
+        let fake_config = Pubkey::new_unique();
+        let config_data = svm.get_account(&config_pda).unwrap().data;
+        set_account_data(&mut svm, fake_config, program_id, config_data);
+        assert_eq!(custom_error(send(&mut svm, stake_ix(fake_config), &[&miner])), Some(56));
+        send(&mut svm, stake_ix(config_pda), &[&miner]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 100);
+        assert_eq!(miner_acc.unstake_ts, 1_060);
+    }
+
+    #[test]
+    fn test_objects_are_assigned_to_staked_miners() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let owner = Keypair::new();
+        svm.airdrop(&owner.pubkey(), 10_000_000_000).unwrap();
+
+        let (global_pda, _) = derive_global_pda(&program_id);
+        let mint = Pubkey::new_unique();
+        set_mint(&mut svm, mint, &global_pda);
+        seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+        svm.set_sysvar(&SlotHashes::new(&[(42, Hash::new_unique())]));
+
+        let owner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 1_000);
+
+        let miners: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+        let miner_pdas: Vec<Pubkey> = miners
+            .iter()
+            .map(|m| {
+                svm.airdrop(&m.pubkey(), 1_000_000_000).unwrap();
+                seed_miner(&mut svm, &program_id, &m.pubkey(), 5_000)
+            })
+            .collect();
+        let unstaked = Pubkey::new_unique();
+        let unstaked_pda = seed_miner(&mut svm, &program_id, &unstaked, 999);
+
+        let (registry_pda, _) = derive_registry_pda(&program_id);
+        let registered = [miner_pdas[0], miner_pdas[1], miner_pdas[2], unstaked_pda];
+        let object_id: u128 = 11;
+        let (object_pda, _) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let register_ix = |replication_factor: u8, candidates: &[Pubkey]| {
+            let mut accounts = vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new(derive_escrow_pda(&program_id, &object_pda).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(registry_pda, false),
+            ];
+            accounts.extend(candidates.iter().map(|c| solana_program::instruction::AccountMeta::new(*c, false)));
+            Instruction::new_with_borsh(
+                program_id,
+                &TapeInstruction::RegisterObject {
+                    commitment: [1u8; 32],
+                    proof_type: 0,
+                    size: 64,
+                    chunk_count: 1,
+                    retention_epochs: 10,
+                    object_id,
+                    replication_factor,
+                },
+                accounts,
+            )
+        };
+
+        // Out-of-range factors are rejected, and the owner must pass every
+        // registered miner in registry order rather than a set of its choosing.
+        assert_eq!(custom_error(send(&mut svm, register_ix(0, &registered), &[&owner])), Some(7));
+        assert_eq!(custom_error(send(&mut svm, register_ix(5, &registered), &[&owner])), Some(7));
+        assert_eq!(custom_error(send(&mut svm, register_ix(2, &miner_pdas), &[&owner])), Some(46));
+        let reordered = [miner_pdas[1], miner_pdas[0], miner_pdas[2], unstaked_pda];
+        assert_eq!(custom_error(send(&mut svm, register_ix(2, &reordered), &[&owner])), Some(46));
+        let duplicated = [miner_pdas[0], miner_pdas[0], miner_pdas[2], unstaked_pda];
+        assert_eq!(custom_error(send(&mut svm, register_ix(2, &duplicated), &[&owner])), Some(46));
+
+        // The under-staked miner is skipped, leaving too few for four replicas.
+        assert_eq!(custom_error(send(&mut svm, register_ix(4, &registered), &[&owner])), Some(8));
+
+        send(&mut svm, register_ix(2, &registered), &[&owner]).unwrap();
+        let registry: MinerRegistry = read_account(&svm, &registry_pda);
+        assert_eq!(registry.draws, 1);
+        // The under-staked miner also gave up its registry slot.
+        let staked: Vec<Pubkey> = miners.iter().map(|m| m.pubkey()).collect();
+        assert_eq!(&registry.miners[..registry.count as usize], &staked[..]);
+
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.replication_factor, 2);
+        let chosen = &object.assigned_miners[..2];
+        assert_ne!(chosen[0], chosen[1]);
+        assert!(chosen.iter().all(|m| miners.iter().any(|k| k.pubkey() == *m)));
+        assert_eq!(object.assigned_miners[2..], [Pubkey::default(); 2]);
+        for (miner, miner_pda) in miners.iter().zip(&miner_pdas) {
+            let miner_acc: MinerAccount = read_account(&svm, miner_pda);
+            assert_eq!(miner_acc.assignments, chosen.contains(&miner.pubkey()) as u64);
+        }
+
+        // One chunk for ten epochs on each of two replicas.
+        assert_eq!(object.deposit, 10 * 10 * 2);
+        assert_eq!(token_balance(&svm, &derive_escrow_pda(&program_id, &object_pda).0), object.deposit);
+        assert_eq!(token_balance(&svm, &owner_token), 1_000 - object.deposit);
+
+        let create_epoch_ix = |miner: &Keypair, miner_pda: Pubkey, epoch_id: u128| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+
+        // Each assigned miner opens its own epoch; the unassigned one cannot.
+        for (miner, miner_pda) in miners.iter().zip(&miner_pdas) {
+            let result = send(&mut svm, create_epoch_ix(miner, *miner_pda, 1), &[miner]);
+            assert_eq!(result.is_ok(), chosen.contains(&miner.pubkey()));
+        }
+
+        let epochs: Vec<EpochRecord> = chosen
+            .iter()
+            .map(|m| read_account(&svm, &derive_epoch_pda(&program_id, &object_pda, m, 1).0))
+            .collect();
+        assert_eq!(epochs[0].miner, chosen[0]);
+        assert_eq!(epochs[1].miner, chosen[1]);
+        assert!(epochs.iter().all(|epoch| epoch.epoch_id == 1));
+    }
+
+    #[test]
+    fn test_only_miners_with_the_minimum_stake_are_registered() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+        let (registry_pda, _) = derive_registry_pda(&program_id);
+
+        let (miner_pda, _) = derive_miner_pda(&program_id, &miner.pubkey());
+        let register_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::RegisterMiner { operator: miner.pubkey(), metadata: [0u8; 64] },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+            ],
+        );
+        send(&mut svm, register_ix, &[&miner]).unwrap();
+
+        // Registering costs no stake, so it takes no registry slot.
+        assert!(svm.get_account(&registry_pda).is_none());
+
+        let miner_token = Pubkey::new_unique();
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 2_000);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 0);
+        let stake_ix = |amount: u64| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Stake { amount },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new(registry_pda, false),
+            ],
+        );
+        let min_stake = ConfigParams::default().min_assignment_stake;
+        send(&mut svm, stake_ix(min_stake - 1), &[&miner]).unwrap();
+        assert!(svm.get_account(&registry_pda).is_none());
+        send(&mut svm, stake_ix(1), &[&miner]).unwrap();
+        let registry: MinerRegistry = read_account(&svm, &registry_pda);
+        assert_eq!(&registry.miners[..registry.count as usize], &[miner.pubkey()]);
+
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = ConfigParams::default().unstake_cooldown_secs;
+        svm.set_sysvar(&clock);
+        let unstake_ix = |amount: u64| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Unstake { amount },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(registry_pda, false),
+            ],
+        );
+
+        // While the miner holds a replica or owes an epoch, only stake above
+        // the minimum can be withdrawn.
+        send(&mut svm, stake_ix(1), &[&miner]).unwrap();
+        let mut miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        miner_acc.assignments = 1;
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&miner_acc));
+        assert_eq!(custom_error(send(&mut svm, unstake_ix(2), &[&miner])), Some(75));
+        miner_acc.assignments = 0;
+        miner_acc.open_epochs = 1;
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&miner_acc));
+        assert_eq!(custom_error(send(&mut svm, unstake_ix(2), &[&miner])), Some(66));
+        send(&mut svm, unstake_ix(1), &[&miner]).unwrap();
+        let mut miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, min_stake);
+
+        // Once it is free of both, dropping below the minimum gives the slot
+        // up again.
+        miner_acc.open_epochs = 0;
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&miner_acc));
+        send(&mut svm, unstake_ix(1), &[&miner]).unwrap();
+        let registry: MinerRegistry = read_account(&svm, &registry_pda);
+        assert_eq!(registry.count, 0);
+    }
+
+    #[test]
+    fn test_owner_escrow_funds_epochs_and_refunds_failures() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let owner = Keypair::new();
+        svm.airdrop(&owner.pubkey(), 10_000_000_000).unwrap();
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+
+        let (global_pda, _) = derive_global_pda(&program_id);
+        let mint = Pubkey::new_unique();
+        set_mint(&mut svm, mint, &global_pda);
+        seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+        svm.set_sysvar(&SlotHashes::new(&[(42, Hash::new_unique())]));
+
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        let (treasury, _) = derive_treasury_pda(&program_id);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 10_000);
+        set_token_account(&mut svm, treasury, &mint, &global_pda, 0);
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 10_000);
+        let miner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 0);
+        let owner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 1_000);
+
+        // A single-chunk object kept for two epochs on one replica.
+        let chunk = vec![3u8; 64];
+        let object_id: u128 = 21;
+        let (object_pda, _) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        let register_ix = |proof_type: u8| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::RegisterObject {
+                commitment: leaf_hash(&chunk),
+                proof_type,
+                size: 64,
+                chunk_count: 1,
+                retention_epochs: 2,
+                object_id,
+                replication_factor: 1,
+            },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+            ],
+        );
+        // Only Merkle-committed objects can be proven on chain.
+        assert_eq!(custom_error(send(&mut svm, register_ix(1), &[&owner])), Some(72));
+        send(&mut svm, register_ix(0), &[&owner]).unwrap();
+
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.epoch_payment, 10);
+        assert_eq!(object.deposit, 20);
+        assert_eq!(token_balance(&svm, &escrow), 20);
+        assert_eq!(token_balance(&svm, &owner_token), 980);
+
+        let create_epoch_ix = |epoch_id: u128| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+
+        // Epoch 1 is proven and finalised: the payment leaves the escrow.
+        send(&mut svm, create_epoch_ix(1), &[&miner]).unwrap();
+        let (epoch_1, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), 1);
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_1);
+        assert_eq!(epoch_rec.payment, 10);
+        let (nonce, _) = reveal_challenges(&mut svm, &epoch_rec, 1);
+        let submit_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::SubmitProof {
+                epoch_id: 1,
+                proof_hash: response_digest(nonce, &[chunk.clone(), chunk.clone()]),
+                proofs: vec![
+                    ChunkProof { chunk: chunk.clone(), path: vec![] },
+                    ChunkProof { chunk: chunk.clone(), path: vec![] },
+                ],
+            },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_1, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+            ],
+        );
+        send(&mut svm, submit_ix, &[&miner]).unwrap();
+
+        // Epoch 2 cannot start before epoch 1's period has run.
+        assert_eq!(custom_error(send(&mut svm, create_epoch_ix(2), &[&miner])), Some(70));
+
+        // Epoch 1 settles once its dispute window has passed.
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = ConfigParams::default().dispute_window_secs;
+        svm.set_sysvar(&clock);
+        let finalize_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::FinalizeEpoch { epoch_id: 1 },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_1, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, finalize_ix, &[&owner]).unwrap();
+        assert_eq!(token_balance(&svm, &miner_token), 10);
+        assert_eq!(token_balance(&svm, &escrow), 10);
+
+        // Epoch 2 is missed: its payment becomes refundable to the owner.
+        send(&mut svm, create_epoch_ix(2), &[&miner]).unwrap();
+        let (epoch_2, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), 2);
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_2);
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = epoch_rec.deadline_ts + 1;
+        svm.set_sysvar(&clock);
+
+        let mark_missed_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::MarkMissed { epoch_id: 2 },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_2, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, mark_missed_ix, &[&owner]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.refundable, 10);
+
+        let refund_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::RefundEscrow { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        );
+        send(&mut svm, refund_ix.clone(), &[&owner]).unwrap();
+        assert_eq!(token_balance(&svm, &owner_token), 990);
+        assert_eq!(token_balance(&svm, &escrow), 0);
+
+        // Nothing is left to refund, and the paid retention is used up.
+        assert!(send(&mut svm, refund_ix, &[&owner]).is_err());
+        assert_eq!(custom_error(send(&mut svm, create_epoch_ix(3), &[&miner])), Some(10));
+
+        // Both epochs are complete, so the object has expired.
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.epochs_completed, [2, 0, 0, 0]);
+
+        // Completed epochs return their rent to the miner that opened them.
+        let close_epoch_ix = |epoch_pda: Pubkey, epoch_id: u128, recipient: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CloseEpoch { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(recipient, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            ],
+        );
+        assert!(send(&mut svm, close_epoch_ix(epoch_1, 1, owner.pubkey()), &[&owner]).is_err());
+        let miner_lamports = svm.get_account(&miner.pubkey()).unwrap().lamports;
+        let epoch_rent = svm.get_account(&epoch_1).unwrap().lamports;
+        send(&mut svm, close_epoch_ix(epoch_1, 1, miner.pubkey()), &[&owner]).unwrap();
+        send(&mut svm, close_epoch_ix(epoch_2, 2, miner.pubkey()), &[&owner]).unwrap();
+        assert!(svm.get_account(&epoch_1).map_or(true, |a| a.lamports == 0));
+        assert!(svm.get_account(&epoch_2).map_or(true, |a| a.lamports == 0));
+        assert_eq!(svm.get_account(&miner.pubkey()).unwrap().lamports, miner_lamports + 2 * epoch_rent);
+
+        // The owner closes the expired object and its escrow, reclaiming the rent.
+        let close_object_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CloseObject { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        );
+        send(&mut svm, close_object_ix, &[&owner]).unwrap();
+        assert!(svm.get_account(&object_pda).map_or(true, |a| a.lamports == 0));
+        assert!(svm.get_account(&escrow).map_or(true, |a| a.lamports == 0));
+        assert_eq!(token_balance(&svm, &owner_token), 990);
+    }
+
+    #[test]
+    fn test_objects_at_the_chunk_limit_can_be_proven() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let owner = Keypair::new();
+        svm.airdrop(&owner.pubkey(), 10_000_000_000).unwrap();
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+
+        let (global_pda, _) = derive_global_pda(&program_id);
+        let mint = Pubkey::new_unique();
+        set_mint(&mut svm, mint, &global_pda);
+        seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+        svm.set_sysvar(&SlotHashes::new(&[(42, Hash::new_unique())]));
+
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 10_000);
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 10_000);
+        let owner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 100_000_000);
+
+        // The largest object is a full tree of depth 23. Its chunks are all
+        // alike, so every node on a level is too and any chunk's path is the
+        // chain of those nodes.
+        let limit = 1u64 << 23;
+        let chunk = vec![7u8; 64];
+        let mut path = vec![leaf_hash(&chunk)];
+        for _ in 1..=23 {
+            let node = path.last().unwrap();
+            path.push(node_hash(node, node));
+        }
+        let root = path.pop().unwrap();
+        let chunk_proof = || ChunkProof { chunk: chunk.clone(), path: path.clone() };
+
+        let register_ix = |object_id: u128, chunk_count: u64| {
+            let (object_pda, _) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+            Instruction::new_with_borsh(
+                program_id,
+                &TapeInstruction::RegisterObject {
+                    commitment: root,
+                    proof_type: 0,
+                    size: chunk_count * 64,
+                    chunk_count,
+                    retention_epochs: 1,
+                    object_id,
+                    replication_factor: 1,
+                },
+                vec![
+                    solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                    solana_program::instruction::AccountMeta::new(object_pda, false),
+                    solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                    solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+                    solana_program::instruction::AccountMeta::new(owner_token, false),
+                    solana_program::instruction::AccountMeta::new(derive_escrow_pda(&program_id, &object_pda).0, false),
+                    solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                    solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                    solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                    solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                    solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+                    solana_program::instruction::AccountMeta::new(miner_pda, false),
+                ],
+            )
+        };
+
+        // One chunk past the limit is refused; the limit itself is accepted.
+        assert_eq!(custom_error(send(&mut svm, register_ix(31, limit + 1), &[&owner])), Some(5));
+        let object_id: u128 = 32;
+        send(&mut svm, register_ix(object_id, limit), &[&owner]).unwrap();
+        let (object_pda, _) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.chunk_count, limit);
+
+        let (epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), 1);
+        let create_epoch_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, create_epoch_ix, &[&miner]).unwrap();
+
+        // Each challenged chunk travels in its own SubmitProof, and with its
+        // full sibling path still fits in a packet.
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        let (nonce, _) = reveal_challenges(&mut svm, &epoch_rec, limit);
+        let proof_hash = response_digest(nonce, &[chunk.clone(), chunk.clone()]);
+        let submit_ix = |proof_hash: [u8; 32]| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::SubmitProof { epoch_id: 1, proof_hash, proofs: vec![chunk_proof()] },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+            ],
+        );
+        let answer_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::AnswerChallenge { epoch_id: 1, proof: chunk_proof() },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new_readonly(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), 1).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        for ix in [submit_ix(proof_hash), answer_ix] {
+            let tx = Transaction::new_signed_with_payer(&[ix], Some(&miner.pubkey()), &[&miner], svm.latest_blockhash());
+            assert!(tx.message.serialize().len() + 1 + 64 <= 1232);
+        }
+
+        send(&mut svm, submit_ix(proof_hash), &[&miner]).unwrap();
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch_rec.status, EpochStatus::Open);
+        assert_eq!(epoch_rec.proven, 1);
+
+        // The digest is checked once the last challenge is proven.
+        assert_eq!(custom_error(send(&mut svm, submit_ix([9u8; 32]), &[&miner])), Some(3));
+        send(&mut svm, submit_ix(proof_hash), &[&miner]).unwrap();
+        let epoch_rec: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch_rec.status, EpochStatus::Submitted);
+        assert_eq!(epoch_rec.proven, 2);
+        assert_eq!(epoch_rec.proof_hash, proof_hash);
+    }
+
+    #[test]
+    fn test_owner_releases_stalled_replicas() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let owner = Keypair::new();
+        svm.airdrop(&owner.pubkey(), 1_000_000_000).unwrap();
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 10_000);
+        svm.set_sysvar(&SlotHashes::new(&[(42, Hash::new_unique())]));
+
+        // One replica served one of its three paid epochs, which ended at
+        // 1_000, and its miner has not opened another since.
+        let epoch_duration = ConfigParams::default().epoch_duration_secs;
+        let object_id: u128 = 6;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let object = ObjectRecord {
+            owner: owner.pubkey(),
+            creator: owner.pubkey(),
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 3,
+            replication_factor: 1,
+            assigned_miners: assigned(miner.pubkey()),
+            epoch_payment: 10,
+            deposit: 30,
+            epochs_opened: [1, 0, 0, 0],
+            epochs_completed: [1, 0, 0, 0],
+            next_epoch_ts: [1_000, 0, 0, 0],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+        let mut miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        miner_acc.assignments = 1;
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&miner_acc));
+
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        let owner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, escrow, &mint, &global_pda, 20);
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 0);
+
+        let release_ix = |signer: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ReleaseReplica { object_id, miner: miner.pubkey() },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer, true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+            ],
+        );
+
+        // The replica is only released a full epoch after its next one was due.
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = 1_000 + epoch_duration - 1;
+        svm.set_sysvar(&clock);
+        assert_eq!(custom_error(send(&mut svm, release_ix(owner.pubkey()), &[&owner])), Some(71));
+
+        clock.unix_timestamp = 1_000 + epoch_duration;
+        svm.set_sysvar(&clock);
+        assert_eq!(custom_error(send(&mut svm, release_ix(miner.pubkey()), &[&miner])), Some(17));
+
+        // The two unserved epochs become refundable and the slot is cleared.
+        let result = send(&mut svm, release_ix(owner.pubkey()), &[&owner]);
+        assert!(result.is_ok());
+        assert_eq!(
+            logged_events(&result),
+            vec![ContractEvent::ReplicaReleased {
+                object_id,
+                miner: miner.pubkey().to_bytes(),
+                refunded: 20,
+                timestamp: 1_000 + epoch_duration,
+            }]
+        );
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.refundable, 20);
+        assert_eq!(object.deposit, 10);
+        assert_eq!(object.assigned_miners, [Pubkey::default(); 4]);
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.assignments, 0);
+
+        // The miner can no longer open epochs for it, and it is released once.
+        let create_epoch_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), 2).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, create_epoch_ix, &[&miner])), Some(9));
+        assert_eq!(custom_error(send(&mut svm, release_ix(owner.pubkey()), &[&owner])), Some(9));
+
+        // With no replica left to serve, the object has expired and closing
+        // it returns the escrow to the owner.
+        let close_object_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CloseObject { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        );
+        send(&mut svm, close_object_ix, &[&owner]).unwrap();
+        assert!(svm.get_account(&object_pda).map_or(true, |a| a.lamports == 0));
+        assert_eq!(token_balance(&svm, &owner_token), 20);
+    }
+
+    #[test]
+    fn test_replicas_hold_their_miner_until_served() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let owner = Keypair::new();
+        svm.airdrop(&owner.pubkey(), 1_000_000_000).unwrap();
+        let miner = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        let (treasury, _) = derive_treasury_pda(&program_id);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 0);
+        set_token_account(&mut svm, treasury, &mint, &global_pda, 0);
+
+        // A one-epoch replica whose only epoch is open and overdue.
+        let object_id: u128 = 8;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let object = ObjectRecord {
+            owner: owner.pubkey(),
+            creator: owner.pubkey(),
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 1,
+            replication_factor: 1,
+            assigned_miners: assigned(miner.pubkey()),
+            epoch_payment: 10,
+            deposit: 10,
+            epochs_opened: [1, 0, 0, 0],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 0);
+        let mut miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        miner_acc.open_epochs = 1;
+        miner_acc.assignments = 1;
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&miner_acc));
+
+        let epoch_id: u128 = 1;
+        let (epoch_pda, epoch_bump) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let epoch = EpochRecord {
+            object_id,
+            object: object_pda,
+            epoch_id,
+            nonce: 0,
+            seed_slot: 0,
+            deadline_ts: 0,
+            challenges: [0, 0],
+            miner: miner.pubkey(),
+            solver: None,
+            proof_hash: [0u8; 32],
+            submitted_at: 0,
+            status: EpochStatus::Open,
+            proven: 0,
+            reward: 0,
+            payment: 10,
+            version: 0,
+            opener: miner.pubkey(),
+            bump: epoch_bump,
+        };
+        set_account_data(&mut svm, epoch_pda, program_id, account_bytes(&epoch));
+
+        // Settling the last epoch of its retention frees the miner.
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = 1;
+        svm.set_sysvar(&clock);
+        let mark_missed_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::MarkMissed { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(treasury, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, mark_missed_ix, &[&owner]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.open_epochs, 0);
+        assert_eq!(miner_acc.assignments, 0);
+
+        // Extending the retention assigns it again, so its account must be
+        // passed along.
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        let owner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, escrow, &mint, &global_pda, 10);
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 10);
+        let extend_ix = |miner_account: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ExtendRetention { object_id, additional_epochs: 1 },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new(miner_account, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, extend_ix(config_pda), &[&owner])), Some(23));
+        send(&mut svm, extend_ix(miner_pda), &[&owner]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.assignments, 1);
+    }
+
+    #[test]
+    fn test_live_objects_and_open_epochs_cannot_be_closed() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let owner = Keypair::new();
+        svm.airdrop(&owner.pubkey(), 1_000_000_000).unwrap();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+
+        let miner = Pubkey::new_unique();
+        let object_id: u128 = 4;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let object = ObjectRecord {
+            owner: owner.pubkey(),
+            creator: owner.pubkey(),
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 2,
+            replication_factor: 1,
+            assigned_miners: assigned(miner),
+            epoch_payment: 10,
+            deposit: 20,
+            epochs_opened: [2, 0, 0, 0],
+            epochs_completed: [1, 0, 0, 0],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        let owner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, escrow, &mint, &global_pda, 10);
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 0);
+
+        let epoch_id: u128 = 2;
+        let (epoch_pda, epoch_bump) = derive_epoch_pda(&program_id, &object_pda, &miner, epoch_id);
+        let epoch = EpochRecord {
+            object_id,
+            object: object_pda,
+            epoch_id,
+            nonce: 1,
+            seed_slot: 0,
+            deadline_ts: i64::MAX,
+            challenges: [0, 0],
+            miner,
+            solver: None,
+            proof_hash: [0u8; 32],
+            submitted_at: 0,
+            status: EpochStatus::Open,
+            proven: 0,
+            reward: 1_000_000,
+            payment: 10,
+            version: 0,
+            opener: miner,
+            bump: epoch_bump,
+        };
+        set_account_data(&mut svm, epoch_pda, program_id, account_bytes(&epoch));
+
+        let close_epoch_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CloseEpoch { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner, epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            ],
+        );
+        let close_object_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CloseObject { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(owner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        );
+
+        // The second epoch is still open, so neither it nor the object can go.
+        assert_eq!(custom_error(send(&mut svm, close_epoch_ix, &[&owner])), Some(12));
+        assert_eq!(custom_error(send(&mut svm, close_object_ix, &[&owner])), Some(11));
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.epochs_completed, [1, 0, 0, 0]);
+        assert_eq!(token_balance(&svm, &escrow), 10);
+    }
+
+    #[test]
+    fn test_retention_extension_and_versioned_commitments() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let owner = Keypair::new();
+        svm.airdrop(&owner.pubkey(), 1_000_000_000).unwrap();
+        let miner = Keypair::new();
+        svm.airdrop(&miner.pubkey(), 1_000_000_000).unwrap();
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+        let config_pda = seed_config(&mut svm, &program_id);
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 10_000);
+
+        let miner_2 = Keypair::new();
+        svm.airdrop(&miner_2.pubkey(), 1_000_000_000).unwrap();
+        let miner_2_pda = seed_miner(&mut svm, &program_id, &miner_2.pubkey(), 10_000);
+        svm.set_sysvar(&SlotHashes::new(&[(42, Hash::new_unique())]));
+
+        // A single-chunk object kept for two epochs on two replicas, the
+        // first replica's first epoch already open.
+        let old_chunk = vec![5u8; 64];
+        let object_id: u128 = 31;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner.pubkey(), object_id);
+        let object = ObjectRecord {
+            owner: owner.pubkey(),
+            creator: owner.pubkey(),
+            object_id,
+            commitment: leaf_hash(&old_chunk),
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 2,
+            replication_factor: 2,
+            assigned_miners: [miner.pubkey(), miner_2.pubkey(), Pubkey::default(), Pubkey::default()],
+            epoch_payment: 10,
+            deposit: 40,
+            epochs_opened: [1, 0, 0, 0],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        let owner_token = Pubkey::new_unique();
+        let miner_token = Pubkey::new_unique();
+        set_token_account(&mut svm, escrow, &mint, &global_pda, 40);
+        set_token_account(&mut svm, owner_token, &mint, &owner.pubkey(), 1_000);
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 0);
+
+        let epoch_id: u128 = 1;
+        let (epoch_pda, epoch_bump) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let epoch = EpochRecord {
+            object_id,
+            object: object_pda,
+            epoch_id,
+            nonce: 0,
+            seed_slot: 0,
+            deadline_ts: i64::MAX,
+            challenges: [0, 0],
+            miner: miner.pubkey(),
+            solver: None,
+            proof_hash: [0u8; 32],
+            submitted_at: 0,
+            status: EpochStatus::Open,
+            proven: 0,
+            reward: 1_000_000,
+            payment: 10,
+            version: 0,
+            opener: miner.pubkey(),
+            bump: epoch_bump,
+        };
+        set_account_data(&mut svm, epoch_pda, program_id, account_bytes(&epoch));
+
+        let owner_accounts = || vec![
+            solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+            solana_program::instruction::AccountMeta::new(object_pda, false),
+            solana_program::instruction::AccountMeta::new(owner_token, false),
+            solana_program::instruction::AccountMeta::new(escrow, false),
+            solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+        ];
+
+        // Three more epochs on two replicas cost 3 * 2 * 10 up front.
+        let extend_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ExtendRetention { object_id, additional_epochs: 3 },
+            owner_accounts(),
+        );
+        send(&mut svm, extend_ix, &[&owner]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.retention_epochs, 5);
+        assert_eq!(object.deposit, 100);
+        assert_eq!(token_balance(&svm, &escrow), 100);
+
+        // Replacing the content with two chunks doubles the price of the nine
+        // epochs not yet opened; the open epoch still challenges version 0.
+        let new_chunks: Vec<Vec<u8>> = vec![vec![6u8; 64], vec![7u8; 64]];
+        let new_leaves = [leaf_hash(&new_chunks[0]), leaf_hash(&new_chunks[1])];
+        let new_root = node_hash(&new_leaves[0], &new_leaves[1]);
+        let update_ix = |commitment: [u8; 32]| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::UpdateCommitment { object_id, commitment, size: 128, chunk_count: 2 },
+            owner_accounts(),
+        );
+        send(&mut svm, update_ix(new_root), &[&owner]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.version, 1);
+        assert_eq!(object.commitment, new_root);
+        assert_eq!(object.previous_versions[0], PreviousVersion { version: 0, commitment: leaf_hash(&old_chunk), size: 64, chunk_count: 1, open_epochs: 1 });
+        assert_eq!(object.epoch_payment, 20);
+        assert_eq!(token_balance(&svm, &escrow), 190);
+        assert_eq!(token_balance(&svm, &owner_token), 1_000 - 60 - 90);
+
+        // The second replica opens its epoch against version 1.
+        let (epoch_2_pda, _) = derive_epoch_pda(&program_id, &object_pda, &miner_2.pubkey(), epoch_id);
+        let create_epoch_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::CreateEpoch { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner_2.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(epoch_2_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_2_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, create_epoch_ix, &[&miner_2]).unwrap();
+        let epoch_2: EpochRecord = read_account(&svm, &epoch_2_pda);
+        assert_eq!(epoch_2.version, 1);
+
+        // A second update in a row keeps both replaced versions while their
+        // epochs are open; a third would drop one of them.
+        send(&mut svm, update_ix([9u8; 32]), &[&owner]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.version, 2);
+        assert_eq!(object.previous_versions[0].version, 0);
+        assert_eq!(object.previous_versions[0].open_epochs, 1);
+        assert_eq!(object.previous_versions[1], PreviousVersion { version: 1, commitment: new_root, size: 128, chunk_count: 2, open_epochs: 1 });
+        assert_eq!(token_balance(&svm, &escrow), 190);
+        assert_eq!(custom_error(send(&mut svm, update_ix([8u8; 32]), &[&owner])), Some(13));
+
+        // Each open epoch is proven against the content it was opened for.
+        let submit_ix = |signer: &Keypair, epoch_pda: Pubkey, miner_pda: Pubkey, proof_hash: [u8; 32], proofs: Vec<ChunkProof>| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::SubmitProof { epoch_id, proof_hash, proofs },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::id(), false),
+            ],
+        );
+        let old_proofs = vec![
+            ChunkProof { chunk: old_chunk.clone(), path: vec![] },
+            ChunkProof { chunk: old_chunk.clone(), path: vec![] },
+        ];
+        let epoch_1: EpochRecord = read_account(&svm, &epoch_pda);
+        let (nonce, _) = reveal_challenges(&mut svm, &epoch_1, 1);
+        let old_response = response_digest(nonce, &[old_chunk.clone(), old_chunk.clone()]);
+        send(&mut svm, submit_ix(&miner, epoch_pda, miner_pda, old_response, old_proofs), &[&miner]).unwrap();
+
+        let (nonce, challenges) = reveal_challenges(&mut svm, &epoch_2, 2);
+        let challenged: Vec<usize> = challenges.iter().map(|&i| i as usize).collect();
+        let new_proofs = challenged.iter().map(|&i| ChunkProof { chunk: new_chunks[i].clone(), path: vec![new_leaves[i ^ 1]] }).collect();
+        let new_response = response_digest(nonce, &[new_chunks[challenged[0]].clone(), new_chunks[challenged[1]].clone()]);
+        send(&mut svm, submit_ix(&miner_2, epoch_2_pda, miner_2_pda, new_response, new_proofs), &[&miner_2]).unwrap();
+
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = ConfigParams::default().dispute_window_secs;
+        svm.set_sysvar(&clock);
+        let finalize_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::FinalizeEpoch { epoch_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(owner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        send(&mut svm, finalize_ix, &[&owner]).unwrap();
+        assert_eq!(token_balance(&svm, &miner_token), 10);
+
+        // With version 0 fully proven its entry is reused; version 1 stays.
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.previous_versions[0].open_epochs, 0);
+        send(&mut svm, update_ix([8u8; 32]), &[&owner]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.version, 3);
+        assert_eq!(object.previous_versions[0], PreviousVersion { version: 2, commitment: [9u8; 32], size: 128, chunk_count: 2, open_epochs: 0 });
+        assert_eq!(object.previous_versions[1].version, 1);
+        assert_eq!(object.previous_versions[1].open_epochs, 1);
+    }
+
+    #[test]
+    fn test_object_ownership_transfers_keep_the_pda() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let creator = Keypair::new();
+        let buyer = Keypair::new();
+        let third = Keypair::new();
+        for k in [&creator, &buyer, &third] {
+            svm.airdrop(&k.pubkey(), 1_000_000_000).unwrap();
+        }
+        let mint = Pubkey::new_unique();
+        let global_pda = seed_global(&mut svm, &program_id, &default_global(Pubkey::new_unique(), mint));
+
+        let object_id: u128 = 8;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &creator.pubkey(), object_id);
+        let object = ObjectRecord {
+            owner: creator.pubkey(),
+            creator: creator.pubkey(),
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 2,
+            replication_factor: 1,
+            assigned_miners: assigned(Pubkey::new_unique()),
+            epoch_payment: 10,
+            deposit: 20,
+            epochs_opened: [1, 0, 0, 0],
+            epochs_completed: [1, 0, 0, 0],
+            next_epoch_ts: [0; 4],
+            refundable: 10,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        set_token_account(&mut svm, escrow, &mint, &global_pda, 20);
+
+        let transfer_ix = |signer: &Keypair, new_owner: Pubkey, require_accept: bool| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::TransferObject { object_id, new_owner, require_accept },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            ],
+        );
+        let accept_ix = |signer: &Keypair| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::AcceptObject { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            ],
+        );
+        let refund_ix = |signer: &Keypair, token: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::RefundEscrow { object_id },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(escrow, false),
+                solana_program::instruction::AccountMeta::new(token, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        );
+
+        // Only the owner can transfer; a two-step transfer waits for acceptance.
+        assert_eq!(custom_error(send(&mut svm, transfer_ix(&buyer, buyer.pubkey(), false), &[&buyer])), Some(17));
+        send(&mut svm, transfer_ix(&creator, buyer.pubkey(), true), &[&creator]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.owner, creator.pubkey());
+        assert_eq!(object.pending_owner, Some(buyer.pubkey()));
+
+        assert_eq!(custom_error(send(&mut svm, accept_ix(&third), &[&third])), Some(18));
+        send(&mut svm, accept_ix(&buyer), &[&buyer]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.owner, buyer.pubkey());
+        assert_eq!(object.creator, creator.pubkey());
+        assert_eq!(object.pending_owner, None);
+
+        // The refund rights move with ownership; the PDA is unchanged.
+        let creator_token = Pubkey::new_unique();
+        let buyer_token = Pubkey::new_unique();
+        set_token_account(&mut svm, creator_token, &mint, &creator.pubkey(), 0);
+        set_token_account(&mut svm, buyer_token, &mint, &buyer.pubkey(), 0);
+        assert!(send(&mut svm, refund_ix(&creator, creator_token), &[&creator]).is_err());
+        send(&mut svm, refund_ix(&buyer, buyer_token), &[&buyer]).unwrap();
+        assert_eq!(token_balance(&svm, &buyer_token), 10);
+
+        // A direct transfer takes effect at once.
+        send(&mut svm, transfer_ix(&buyer, third.pubkey(), false), &[&buyer]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.owner, third.pubkey());
+        assert!(send(&mut svm, transfer_ix(&buyer, buyer.pubkey(), false), &[&buyer]).is_err());
+    }
+
+    #[test]
+    fn test_spoofed_accounts_are_rejected() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        let miner = Keypair::new();
+        let attacker = Keypair::new();
+        for key in [&payer, &miner, &attacker] {
+            svm.airdrop(&key.pubkey(), 1_000_000_000).unwrap();
+        }
+        let mint = Pubkey::new_unique();
+        let global_state = GlobalState {
+            epoch_reward: 1_000,
+            ..default_global(payer.pubkey(), mint)
+        };
+        let global_pda = seed_global(&mut svm, &program_id, &global_state);
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 500);
+        let attacker_miner = seed_miner(&mut svm, &program_id, &attacker.pubkey(), 100);
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        let attacker_vault = Pubkey::new_unique();
+        let miner_token = Pubkey::new_unique();
+        let attacker_token = Pubkey::new_unique();
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 600);
+        set_token_account(&mut svm, attacker_vault, &mint, &attacker.pubkey(), 0);
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 0);
+        set_token_account(&mut svm, attacker_token, &mint, &attacker.pubkey(), 100);
+
+        let (object_id, epoch_id) = (1u128, 1u128);
+        let owner = Pubkey::new_unique();
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner, object_id);
+        let object = ObjectRecord {
+            owner,
+            creator: owner,
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 64,
+            chunk_count: 1,
+            created_ts: 0,
+            retention_epochs: 10,
+            replication_factor: 1,
+            assigned_miners: assigned(miner.pubkey()),
+            epoch_payment: 0,
+            deposit: 0,
+            epochs_opened: [1, 0, 0, 0],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+        // Object ids are chosen per creator, so the attacker can hold a valid
+        // object under the same id.
+        let (attacker_object, attacker_object_bump) = derive_object_pda(&program_id, &attacker.pubkey(), object_id);
+        set_account_data(&mut svm, attacker_object, program_id, account_bytes(&ObjectRecord {
+            owner: attacker.pubkey(),
+            creator: attacker.pubkey(),
+            bump: attacker_object_bump,
+            ..object
+        }));
+
+        let (epoch_pda, epoch_bump) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let epoch = EpochRecord {
+            object_id,
+            object: object_pda,
+            epoch_id,
+            nonce: 0,
+            seed_slot: 0,
+            deadline_ts: 0,
+            challenges: [0; 2],
+            miner: miner.pubkey(),
+            solver: Some(miner.pubkey()),
+            proof_hash: [0u8; 32],
+            submitted_at: 0,
+            status: EpochStatus::Submitted,
+            proven: 2,
+            reward: 1_000,
+            payment: 0,
+            version: 0,
+            opener: miner.pubkey(),
+            bump: epoch_bump,
+        };
+        set_account_data(&mut svm, epoch_pda, program_id, account_bytes(&epoch));
+
+        // The same epoch bytes, held by another program or at another address.
+        let foreign_epoch = Pubkey::new_unique();
+        set_account_data(&mut svm, foreign_epoch, Pubkey::new_unique(), account_bytes(&epoch));
+        let copied_epoch = Pubkey::new_unique();
+        set_account_data(&mut svm, copied_epoch, program_id, account_bytes(&epoch));
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = ConfigParams::default().dispute_window_secs;
+        svm.set_sysvar(&clock);
+
+        let finalize_accounts = || vec![
+            solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+            solana_program::instruction::AccountMeta::new(epoch_pda, false),
+            solana_program::instruction::AccountMeta::new(miner_pda, false),
+            solana_program::instruction::AccountMeta::new(global_pda, false),
+            solana_program::instruction::AccountMeta::new(object_pda, false),
+            solana_program::instruction::AccountMeta::new(derive_escrow_pda(&program_id, &object_pda).0, false),
+            solana_program::instruction::AccountMeta::new(miner_token, false),
+            solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+        ];
+        let finalize_ix = |accounts: Vec<solana_program::instruction::AccountMeta>| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::FinalizeEpoch { epoch_id },
+            accounts,
+        );
+        let with = |index: usize, meta: solana_program::instruction::AccountMeta| {
+            let mut accounts = finalize_accounts();
+            accounts[index] = meta;
+            accounts
+        };
+
+        let spoofs = [
+            (with(1, solana_program::instruction::AccountMeta::new(foreign_epoch, false)), 61),
+            (with(1, solana_program::instruction::AccountMeta::new(copied_epoch, false)), 22),
+            (with(1, solana_program::instruction::AccountMeta::new_readonly(epoch_pda, false)), 62),
+            (with(2, solana_program::instruction::AccountMeta::new(attacker_miner, false)), 28),
+            (with(4, solana_program::instruction::AccountMeta::new(attacker_object, false)), 21),
+            (with(6, solana_program::instruction::AccountMeta::new(attacker_token, false)), 29),
+            (with(7, solana_program::instruction::AccountMeta::new_readonly(Pubkey::new_unique(), false)), 63),
+        ];
+        for (accounts, code) in spoofs {
+            assert_eq!(custom_error(send(&mut svm, finalize_ix(accounts), &[&payer])), Some(code));
+        }
+
+        // A status byte the program never writes is rejected as bad account
+        // data rather than aborting the program.
+        let mut corrupt = account_bytes(&epoch);
+        corrupt[contract::EpochRecord::STATUS_OFFSET] = 9;
+        set_account_data(&mut svm, epoch_pda, program_id, corrupt);
+        let failed = send(&mut svm, finalize_ix(finalize_accounts()), &[&payer]).unwrap_err();
+        assert_eq!(failed.err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+        set_account_data(&mut svm, epoch_pda, program_id, account_bytes(&epoch));
+
+        // A challenge must target the epoch PDA itself.
+        let challenge_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::ChallengeProof { epoch_id, chunk_index: 3 },
+            vec![
+                solana_program::instruction::AccountMeta::new(attacker.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(copied_epoch, false),
+                solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_challenge_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id).0, false),
+                solana_program::instruction::AccountMeta::new(attacker_token, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, challenge_ix, &[&attacker])), Some(22));
+
+        // Stake only moves between a miner's own account and its own vault.
+        let unstake_ix = |miner_account: Pubkey, vault: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Unstake { amount: 100 },
+            vec![
+                solana_program::instruction::AccountMeta::new(attacker.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_account, false),
+                solana_program::instruction::AccountMeta::new(vault, false),
+                solana_program::instruction::AccountMeta::new(attacker_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, unstake_ix(miner_pda, stake_vault), &[&attacker])), Some(23));
+        assert_eq!(custom_error(send(&mut svm, unstake_ix(attacker_miner, attacker_vault), &[&attacker])), Some(64));
+        assert_eq!(custom_error(send(&mut svm, unstake_ix(attacker_miner, stake_vault), &[&attacker])), Some(64));
+
+        let stake_ix = |vault: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Stake { amount: 100 },
+            vec![
+                solana_program::instruction::AccountMeta::new(attacker.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(attacker_token, false),
+                solana_program::instruction::AccountMeta::new(vault, false),
+                solana_program::instruction::AccountMeta::new(attacker_miner, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, stake_ix(attacker_vault), &[&attacker])), Some(64));
+        assert_eq!(custom_error(send(&mut svm, stake_ix(stake_vault), &[&attacker])), Some(64));
+
+        let slash_ix = Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Slash { miner: miner.pubkey(), amount: 100 },
+            vec![
+                solana_program::instruction::AccountMeta::new(payer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new(attacker_vault, false),
+                solana_program::instruction::AccountMeta::new(derive_treasury_pda(&program_id).0, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ],
+        );
+        assert_eq!(custom_error(send(&mut svm, slash_ix, &[&payer])), Some(64));
+
+        // Nothing above moved stake or credited rewards; the real accounts still work.
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 500);
+        assert_eq!(miner_acc.pending_rewards, 0);
+        assert_eq!(token_balance(&svm, &stake_vault), 600);
+        send(&mut svm, finalize_ix(finalize_accounts()), &[&payer]).unwrap();
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.pending_rewards, 1_000);
+    }
+
+    #[test]
+    fn test_legacy_accounts_are_migrated_in_place() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let admin = Keypair::new();
+        let miner = Keypair::new();
+        for key in [&admin, &miner] {
+            svm.airdrop(&key.pubkey(), 1_000_000_000).unwrap();
+        }
+
+        // The first deployment wrote bare Borsh bodies with no header.
+        let (global_pda, _) = derive_global_pda(&program_id);
+        let mint = Pubkey::new_unique();
+        set_mint(&mut svm, mint, &global_pda);
+        let legacy_global = borsh::to_vec(&GlobalStateV0 {
+            admin: admin.pubkey(),
+            reward_mint: mint,
+            reward_vault: Pubkey::new_unique(),
+            total_minted: 0,
+            emission_cap: 1_000_000_000,
+            decay_numerator: 9,
+            decay_denom: 10,
+            last_decay_at: 0,
+        }).unwrap();
+        set_account_data(&mut svm, global_pda, program_id, legacy_global);
+        let config_pda = seed_config(&mut svm, &program_id);
+        let (miner_pda, miner_bump) = derive_miner_pda(&program_id, &miner.pubkey());
+        let legacy_miner = borsh::to_vec(&MinerAccountV0 {
+            miner: miner.pubkey(),
+            stake: 0,
+            pending_rewards: 0,
+            reputation: 100,
+            unstake_ts: 0,
+            bump: miner_bump,
+        }).unwrap();
+        set_account_data(&mut svm, miner_pda, program_id, legacy_miner);
+
+        let miner_token = Pubkey::new_unique();
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 100);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 0);
+        let stake_ix = || Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::Stake { amount: 50 },
+            vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+            ],
+        );
+        let migrate_ix = |account: Pubkey, kind: AccountKind, extra: Vec<solana_program::instruction::AccountMeta>| {
+            let mut accounts = vec![
+                solana_program::instruction::AccountMeta::new(admin.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(account, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+            ];
+            accounts.extend(extra);
+            Instruction::new_with_borsh(program_id, &TapeInstruction::Migrate { kind }, accounts)
+        };
+
+        // Untagged accounts are refused until migrated.
+        assert_eq!(custom_error(send(&mut svm, stake_ix(), &[&miner])), Some(58));
+        send(&mut svm, migrate_ix(global_pda, AccountKind::Global, vec![]), &[&admin]).unwrap();
+        let global: GlobalState = read_account(&svm, &global_pda);
+        assert_eq!(global.tape_mint, mint);
+        assert_eq!(global.emission_mode, 0);
+        assert_eq!(global.decay_n, 9);
+        assert_eq!(global.epoch_reward, 1_000_000);
+        assert_eq!(&global.admins[..global.admin_count as usize], &[admin.pubkey()]);
+        assert_eq!(global.admin_threshold, 1);
+        assert_eq!(custom_error(send(&mut svm, stake_ix(), &[&miner])), Some(58));
+
+        // The account is named by its seeds, so another type is rejected.
+        assert!(send(&mut svm, migrate_ix(miner_pda, AccountKind::Global, vec![]), &[&admin]).is_err());
+
+        send(&mut svm, migrate_ix(miner_pda, AccountKind::Miner, vec![]), &[&admin]).unwrap();
+        let account = svm.get_account(&miner_pda).unwrap();
+        assert!(account.lamports >= svm.minimum_balance_for_rent_exemption(account.data.len()));
+        let migrated: MinerAccount = decode_account(&account.data);
+        assert_eq!(account.data.len(), account_bytes(&migrated).len());
+        assert_eq!(migrated.miner, miner.pubkey());
+        assert_eq!(migrated.operator, miner.pubkey());
+        assert_eq!(migrated.reputation, 100);
+        assert_eq!(migrated.open_epochs, 0);
+        // Migrated miners only join the registry once they stake the minimum.
+        assert!(svm.get_account(&derive_registry_pda(&program_id).0).is_none());
+        assert!(logged_events(&send(&mut svm, stake_ix(), &[&miner])).iter().any(|e| matches!(e, ContractEvent::Staked { stake: 50, .. })));
+        assert_eq!(custom_error(send(&mut svm, migrate_ix(miner_pda, AccountKind::Miner, vec![]), &[&admin])), Some(60));
+
+        // A body tagged as another type does not load as a miner.
+        let config: Config = read_account(&svm, &config_pda);
+        set_account_data(&mut svm, miner_pda, program_id, account_bytes(&config));
+        assert_eq!(custom_error(send(&mut svm, stake_ix(), &[&miner])), Some(58));
+
+        // Layout versions the program does not know are refused.
+        let mut outdated = account.data.clone();
+        outdated[8] = 0;
+        set_account_data(&mut svm, miner_pda, program_id, outdated);
+        assert_eq!(custom_error(send(&mut svm, stake_ix(), &[&miner])), Some(59));
+        assert_eq!(custom_error(send(&mut svm, migrate_ix(miner_pda, AccountKind::Miner, vec![]), &[&admin])), Some(59));
+        set_account_data(&mut svm, miner_pda, program_id, account.data.clone());
+
+        // Objects did not store their id, so the caller names it; the seeds
+        // confirm it. The migrated object gets an empty escrow.
+        let object_id: u128 = 8;
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &admin.pubkey(), object_id);
+        let legacy_object = borsh::to_vec(&ObjectRecordV0 {
+            owner: admin.pubkey(),
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 100,
+            created_ts: 5,
+            retention_epochs: 10,
+            bump: object_bump,
+        }).unwrap();
+        set_account_data(&mut svm, object_pda, program_id, legacy_object);
+        let (escrow, _) = derive_escrow_pda(&program_id, &object_pda);
+        let object_accounts = || vec![
+            solana_program::instruction::AccountMeta::new(escrow, false),
+            solana_program::instruction::AccountMeta::new_readonly(mint, false),
+            solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ];
+        assert_eq!(custom_error(send(&mut svm, migrate_ix(object_pda, AccountKind::Object { object_id: 9 }, object_accounts()), &[&admin])), Some(21));
+        send(&mut svm, migrate_ix(object_pda, AccountKind::Object { object_id }, object_accounts()), &[&admin]).unwrap();
+        let object: ObjectRecord = read_account(&svm, &object_pda);
+        assert_eq!(object.object_id, object_id);
+        assert_eq!(object.creator, admin.pubkey());
+        assert_eq!(object.commitment, [1u8; 32]);
+        assert_eq!(object.chunk_count, 2);
+        assert_eq!(object.retention_epochs, 10);
+        assert_eq!(object.replication_factor, 0);
+        assert_eq!(token_balance(&svm, &escrow), 0);
+
+        // Epochs were seeded by object id and epoch id. A settled one is
+        // closed; a submitted one moves to the account seeded by its object
+        // and solver, still owing its reward.
+        let legacy_epoch = |epoch_id: u128, status: EpochStatus| {
+            let eid = epoch_id.to_le_bytes();
+            let (pda, bump) = Pubkey::find_program_address(&[b"epoch", &object_id.to_le_bytes(), &eid], &program_id);
+            let body = borsh::to_vec(&EpochRecordV0 {
+                object_id,
+                epoch_id,
+                nonce: 7,
+                deadline_ts: 120,
+                solver: Some(miner.pubkey()),
+                proof_hash: [3u8; 32],
+                status,
+                reward: 1_000_000,
+                bump,
+            }).unwrap();
+            (pda, body)
+        };
+        let (finalized_pda, finalized) = legacy_epoch(4, EpochStatus::Finalized);
+        set_account_data(&mut svm, finalized_pda, program_id, finalized);
+        send(&mut svm, migrate_ix(finalized_pda, AccountKind::Epoch, vec![]), &[&admin]).unwrap();
+        assert!(svm.get_account(&finalized_pda).map_or(true, |a| a.lamports == 0));
+
+        let epoch_id: u128 = 3;
+        let (submitted_pda, submitted) = legacy_epoch(epoch_id, EpochStatus::Submitted);
+        let (epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let epoch_accounts = |epoch_pda: Pubkey| vec![
+            solana_program::instruction::AccountMeta::new_readonly(object_pda, false),
+            solana_program::instruction::AccountMeta::new(epoch_pda, false),
+            solana_program::instruction::AccountMeta::new(miner_pda, false),
+        ];
+        let stray = Pubkey::new_unique();
+        set_account_data(&mut svm, stray, program_id, submitted.clone());
+        assert_eq!(custom_error(send(&mut svm, migrate_ix(stray, AccountKind::Epoch, epoch_accounts(epoch_pda)), &[&admin])), Some(22));
+        set_account_data(&mut svm, submitted_pda, program_id, submitted);
+        let elsewhere = derive_epoch_pda(&program_id, &object_pda, &admin.pubkey(), epoch_id).0;
+        assert_eq!(custom_error(send(&mut svm, migrate_ix(submitted_pda, AccountKind::Epoch, epoch_accounts(elsewhere)), &[&admin])), Some(22));
+
+        send(&mut svm, migrate_ix(submitted_pda, AccountKind::Epoch, epoch_accounts(epoch_pda)), &[&admin]).unwrap();
+        assert!(svm.get_account(&submitted_pda).map_or(true, |a| a.lamports == 0));
+        let epoch: EpochRecord = read_account(&svm, &epoch_pda);
+        assert_eq!(epoch.object, object_pda);
+        assert_eq!(epoch.miner, miner.pubkey());
+        assert_eq!(epoch.solver, Some(miner.pubkey()));
+        assert_eq!(epoch.status, EpochStatus::Submitted);
+        assert_eq!(epoch.reward, 1_000_000);
+        assert_eq!(epoch.payment, 0);
+        assert_eq!(epoch.opener, admin.pubkey());
+        let migrated: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(migrated.open_epochs, 1);
+    }
+
+    #[test]
+    fn test_legacy_stake_moves_into_the_miner_vault() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let admin = Keypair::new();
+        let outsider = Keypair::new();
+        for key in [&admin, &outsider] {
+            svm.airdrop(&key.pubkey(), 1_000_000_000).unwrap();
+        }
+
+        let (global_pda, _) = derive_global_pda(&program_id);
+        let mint = Pubkey::new_unique();
+        set_mint(&mut svm, mint, &global_pda);
+        seed_global(&mut svm, &program_id, &default_global(admin.pubkey(), mint));
+
+        // Before per-miner vaults, stake was deposited into a shared account
+        // held by the global PDA and only recorded on the miner account.
+        let miner = Pubkey::new_unique();
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner, 3_000);
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner);
+        let legacy_vault = Pubkey::new_unique();
+        set_token_account(&mut svm, legacy_vault, &mint, &global_pda, 10_000);
+        let foreign_vault = Pubkey::new_unique();
+        set_token_account(&mut svm, foreign_vault, &mint, &outsider.pubkey(), 10_000);
+
+        let migrate_stake_ix = |signer: &Keypair, source: Pubkey| Instruction::new_with_borsh(
+            program_id,
+            &TapeInstruction::MigrateStake { miner },
+            vec![
+                solana_program::instruction::AccountMeta::new(signer.pubkey(), true),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(source, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+            ],
+        );
+
+        // The move needs the admin quorum and a source the global PDA holds.
+        assert_eq!(custom_error(send(&mut svm, migrate_stake_ix(&outsider, legacy_vault), &[&outsider])), Some(53));
+        assert_eq!(custom_error(send(&mut svm, migrate_stake_ix(&admin, foreign_vault), &[&admin])), Some(29));
+
+        // The recorded stake moves into the miner's vault, created on the way.
+        let result = send(&mut svm, migrate_stake_ix(&admin, legacy_vault), &[&admin]);
+        assert!(result.is_ok());
+        let clock: Clock = svm.get_sysvar();
+        assert_eq!(logged_events(&result), vec![ContractEvent::StakeMigrated {
+            miner: miner.to_bytes(),
+            amount: 3_000,
+            timestamp: clock.unix_timestamp,
+        }]);
+        assert_eq!(token_balance(&svm, &stake_vault), 3_000);
+        assert_eq!(token_balance(&svm, &legacy_vault), 7_000);
+        let miner_acc: MinerAccount = read_account(&svm, &miner_pda);
+        assert_eq!(miner_acc.stake, 3_000);
+
+        // Each miner is migrated once.
+        assert_eq!(custom_error(send(&mut svm, migrate_stake_ix(&admin, legacy_vault), &[&admin])), Some(60));
+        assert_eq!(token_balance(&svm, &legacy_vault), 7_000);
+    }
+
+    /// Ceiling for each instruction on the per-epoch path; the default budget
+    /// is 200_000 per instruction.
+    const HOT_PATH_CU_LIMIT: u64 = 60_000;
+
+    /// Per-instruction units from the file named by CU_BASELINE, one
+    /// `<instruction> <units>` line each, as measured with the Borsh layouts
+    /// the zero-copy ones replaced. No baseline is checked in: it has to be
+    /// measured on a build of that commit.
+    fn cu_baseline() -> std::collections::HashMap<String, u64> {
+        let Ok(path) = std::env::var("CU_BASELINE") else {
+            return Default::default();
+        };
+        let contents = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {path}: {e}"));
+        contents
+            .lines()
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .map(|(name, units)| (name.to_string(), units.trim().parse().unwrap_or_else(|_| panic!("bad units for {name}"))))
+            .collect()
+    }
+
+    #[test]
+    fn test_hot_path_compute_units() {
+        let mut svm = LiteSVM::new();
+        let program_id = load_program(&mut svm);
+
+        let payer = Keypair::new();
+        let miner = Keypair::new();
+        for key in [&payer, &miner] {
+            svm.airdrop(&key.pubkey(), 10_000_000_000).unwrap();
+        }
+
+        let reward = 1_000_000u64;
+        let mint = Pubkey::new_unique();
+        let reward_vault = Pubkey::new_unique();
+        let global_state = GlobalState {
+            reward_vault,
+            epoch_reward: reward,
+            ..default_global(payer.pubkey(), mint)
+        };
+        let global_pda = seed_global(&mut svm, &program_id, &global_state);
+        set_token_account(&mut svm, reward_vault, &mint, &global_pda, 5_000_000);
+        let config_pda = seed_config(&mut svm, &program_id);
+
+        let miner_pda = seed_miner(&mut svm, &program_id, &miner.pubkey(), 0);
+        let miner_token = Pubkey::new_unique();
+        let (stake_vault, _) = derive_stake_vault_pda(&program_id, &miner.pubkey());
+        set_token_account(&mut svm, miner_token, &mint, &miner.pubkey(), 100);
+        set_token_account(&mut svm, stake_vault, &mint, &global_pda, 0);
+
+        // One submitted epoch to finalise and room to open the next.
+        let (object_id, epoch_id) = (1u128, 1u128);
+        let owner = Pubkey::new_unique();
+        let (object_pda, object_bump) = derive_object_pda(&program_id, &owner, object_id);
+        let object = ObjectRecord {
+            owner,
+            creator: owner,
+            object_id,
+            commitment: [1u8; 32],
+            proof_type: 0,
+            size: 4096,
+            chunk_count: 64,
+            created_ts: 0,
+            retention_epochs: 10,
+            replication_factor: 1,
+            assigned_miners: assigned(miner.pubkey()),
+            epoch_payment: 0,
+            deposit: 0,
+            epochs_opened: [1, 0, 0, 0],
+            epochs_completed: [0; 4],
+            next_epoch_ts: [0; 4],
+            refundable: 0,
+            version: 0,
+            previous_versions: Default::default(),
+            pending_owner: None,
+            bump: object_bump,
+        };
+        set_account_data(&mut svm, object_pda, program_id, account_bytes(&object));
+
+        let (epoch_pda, epoch_bump) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id);
+        let epoch = EpochRecord {
+            object_id,
+            object: object_pda,
+            epoch_id,
+            nonce: 0,
+            seed_slot: 0,
+            deadline_ts: 0,
+            challenges: [0; 2],
+            miner: miner.pubkey(),
+            solver: Some(miner.pubkey()),
+            proof_hash: [0u8; 32],
+            submitted_at: 0,
+            status: EpochStatus::Submitted,
+            proven: 2,
+            reward,
+            payment: 0,
+            version: 0,
+            opener: miner.pubkey(),
+            bump: epoch_bump,
+        };
+        set_account_data(&mut svm, epoch_pda, program_id, account_bytes(&epoch));
+        let (next_epoch_pda, _) = derive_epoch_pda(&program_id, &object_pda, &miner.pubkey(), epoch_id + 1);
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = ConfigParams::default().dispute_window_secs;
+        svm.set_sysvar(&clock);
+
+        let instructions = [
+            ("Stake", TapeInstruction::Stake { amount: 50 }, vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new(stake_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new_readonly(mint, false),
+                solana_program::instruction::AccountMeta::new(derive_registry_pda(&program_id).0, false),
+            ]),
+            ("CreateEpoch", TapeInstruction::CreateEpoch { object_id }, vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(next_epoch_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(system_program, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ]),
+            ("FinalizeEpoch", TapeInstruction::FinalizeEpoch { epoch_id }, vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(epoch_pda, false),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(global_pda, false),
+                solana_program::instruction::AccountMeta::new(object_pda, false),
+                solana_program::instruction::AccountMeta::new(derive_escrow_pda(&program_id, &object_pda).0, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(config_pda, false),
+            ]),
+            ("ClaimRewards", TapeInstruction::ClaimRewards, vec![
+                solana_program::instruction::AccountMeta::new(miner.pubkey(), true),
+                solana_program::instruction::AccountMeta::new(miner_pda, false),
+                solana_program::instruction::AccountMeta::new(reward_vault, false),
+                solana_program::instruction::AccountMeta::new(miner_token, false),
+                solana_program::instruction::AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                solana_program::instruction::AccountMeta::new_readonly(global_pda, false),
+            ]),
+        ];
+
+        let baseline = cu_baseline();
+        for (name, data, accounts) in instructions {
+            let ix = Instruction::new_with_borsh(program_id, &data, accounts);
+            let meta = send(&mut svm, ix, &[&miner]).unwrap_or_else(|e| panic!("{name} failed: {:?}", e.err));
+            let units = meta.compute_units_consumed;
+            assert!(units < HOT_PATH_CU_LIMIT, "{name} used {units} CU");
+            if let Some(&before) = baseline.get(name) {
+                assert!(units < before, "{name} used {units} CU, baseline {before}");
+            }
+        }
+
+        // Fields sit at fixed offsets, so RPC memcmp filters can select them.
+        let object_data = svm.get_account(&object_pda).unwrap().data;
+        assert_eq!(object_data[contract::ObjectRecord::OWNER_OFFSET..][..32], owner.to_bytes());
+        assert_eq!(object_data[contract::ObjectRecord::CREATOR_OFFSET..][..32], owner.to_bytes());
+        let epoch_data = svm.get_account(&epoch_pda).unwrap().data;
+        assert_eq!(epoch_data[contract::EpochRecord::OBJECT_ID_OFFSET..][..16], object_id.to_le_bytes());
+        assert_eq!(epoch_data[contract::EpochRecord::MINER_OFFSET..][..32], miner.pubkey().to_bytes());
+        assert_eq!(epoch_data[contract::EpochRecord::STATUS_OFFSET], EpochStatus::Finalized as u8);
+        let miner_data = svm.get_account(&miner_pda).unwrap().data;
+        assert_eq!(miner_data[contract::MinerAccount::MINER_OFFSET..][..32], miner.pubkey().to_bytes());
+        assert_eq!(miner_data[contract::MinerAccount::OPERATOR_OFFSET..][..32], miner.pubkey().to_bytes());
+    }
+}